@@ -0,0 +1,8 @@
+-- User demo1
+INSERT INTO "user" (username) VALUES ('demo1');
+
+-- User admin (the user management)
+INSERT INTO "user" (username, admin) VALUES ('admin', true);
+
+-- User demo2 (another non admin user, e.g., for the access tests)
+INSERT INTO "user" (username) VALUES ('demo2');
\ No newline at end of file
@@ -0,0 +1,93 @@
+---- Base app schema
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+-- User
+CREATE TABLE "user" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  username varchar(128) NOT NULL UNIQUE,
+  pwd varchar(256),
+  pwd_salt uuid NOT NULL DEFAULT uuid_generate_v4(),
+  token_salt uuid NOT NULL DEFAULT uuid_generate_v4(),
+  admin boolean NOT NULL DEFAULT false,
+  disabled boolean NOT NULL DEFAULT false,
+  -- incremented on every update (optimistic concurrency)
+  version BIGINT NOT NULL DEFAULT 1
+);
+-- User Profile
+CREATE TABLE "user_profile" (
+  id BIGINT PRIMARY KEY REFERENCES "user"(id) ON DELETE CASCADE,
+  display_name varchar(128),
+  email varchar(256),
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  version BIGINT NOT NULL DEFAULT 1
+);
+-- Task
+CREATE TYPE task_status AS ENUM ('todo', 'in_progress', 'blocked', 'done');
+Create TABLE "task" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  title varchar(256) NOT NULL,
+  description text,
+  due_at timestamp with time zone,
+  priority integer NOT NULL DEFAULT 0,
+  status task_status NOT NULL DEFAULT 'todo',
+  -- derived from status, so they cannot disagree
+  done boolean GENERATED ALWAYS AS (status = 'done') STORED,
+  assignee_id BIGINT REFERENCES "user"(id) ON DELETE SET NULL,
+  parent_id BIGINT REFERENCES "task"(id) ON DELETE SET NULL,
+  deleted_at timestamp with time zone,
+  version BIGINT NOT NULL DEFAULT 1,
+  -- full-text search (see TaskBmc::search, same 'english' config)
+  search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', title), 'A') ||
+    setweight(to_tsvector('english', coalesce(description, '')), 'B')
+  ) STORED
+);
+CREATE INDEX task_search_idx ON "task" USING GIN (search);
+-- Task Dependency (task_id is blocked by depends_on_id)
+CREATE TABLE "task_dependency" (
+  task_id BIGINT NOT NULL REFERENCES "task"(id) ON DELETE CASCADE,
+  depends_on_id BIGINT NOT NULL REFERENCES "task"(id) ON DELETE CASCADE,
+  PRIMARY KEY (task_id, depends_on_id),
+  CHECK (task_id <> depends_on_id)
+);
+-- Task Watcher
+CREATE TABLE "task_watcher" (
+  task_id BIGINT NOT NULL REFERENCES "task"(id) ON DELETE CASCADE,
+  user_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  PRIMARY KEY (task_id, user_id)
+);
+-- Comment
+CREATE TABLE "comment" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  task_id BIGINT NOT NULL REFERENCES "task"(id) ON DELETE CASCADE,
+  author_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  content text NOT NULL,
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  mtime timestamp with time zone NOT NULL DEFAULT now(),
+  version BIGINT NOT NULL DEFAULT 1
+);
+-- Label
+CREATE TABLE "label" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  name varchar(128) NOT NULL UNIQUE,
+  color varchar(32),
+  version BIGINT NOT NULL DEFAULT 1
+);
+-- Task Label
+CREATE TABLE "task_label" (
+  task_id BIGINT NOT NULL REFERENCES "task"(id) ON DELETE CASCADE,
+  label_id BIGINT NOT NULL REFERENCES "label"(id) ON DELETE CASCADE,
+  PRIMARY KEY (task_id, label_id)
+);
+-- Entity History (append-only, no update/delete)
+CREATE TABLE "entity_history" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  entity varchar(64) NOT NULL,
+  entity_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  op varchar(16) NOT NULL,
+  changes jsonb,
+  ctime timestamp with time zone NOT NULL DEFAULT now()
+);
+CREATE INDEX entity_history_entity_idx ON "entity_history" (entity, entity_id);
+CREATE RULE entity_history_no_update AS ON UPDATE TO "entity_history" DO INSTEAD NOTHING;
+CREATE RULE entity_history_no_delete AS ON DELETE TO "entity_history" DO INSTEAD NOTHING;
\ No newline at end of file
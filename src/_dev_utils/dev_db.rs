@@ -2,7 +2,7 @@ use std::{fs, path::PathBuf, time::Duration};
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 use tracing::info;
 
-use crate::{ctx::Ctx, model::{user::{User, UserBmc}, ModelManager}};
+use crate::{config, ctx::Ctx, model::{migration, user::{User, UserBmc}, ModelManager}, Error};
 
 type Db = Pool<Postgres>;
 
@@ -21,13 +21,31 @@ const DEMO_PWD: &str = "demo_pwd";
 pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
 	info!("{:<12} - dev_db.rs:21", "FOR DEV ONLY");
 
+	// -- Refuse to drop anything but the local dev db
+	if !config().RUN_MODE.allows_dev_db() {
+		return Err(Error::DevDbRefused {
+			reason: "SERVICE_RUN_MODE is not dev or test",
+		}
+		.into());
+	}
+	if config().DB_URL != PG_DEV_APP_URL {
+		return Err(Error::DevDbRefused {
+			reason: "SERVICE_DB_URL is not the dev db",
+		}
+		.into());
+	}
+
 	// -- Create the app_db/app_user with the postgres user
 	{
 		let root_db = new_db_pool(PG_DEV_POSTGRES_URL).await?;
 		pexec(&root_db, SQL_RECREATE_DB).await?;
 	}
 
-	// -- Get sql files
+	// -- Create the schema
+	let mm = ModelManager::new().await?;
+	mm.migrate().await?;
+
+	// -- Get sql files (the dev seed)
 	let mut paths: Vec<PathBuf> = fs::read_dir(SQL_DIR)?
 		.filter_map(|entry| entry.ok().map(|e| e.path()))
 		.collect();
@@ -49,10 +67,14 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
 
   // update pwd for demo1
   let ctx = Ctx::root_ctx();
-  let mm = ModelManager::new().await?;
   let demo1_user: User = UserBmc::first_by_username(&ctx, &mm, "demo1").await?.unwrap();
   UserBmc::update_pwd(&ctx, &mm, demo1_user.id, DEMO_PWD).await?;
   info!("{:<12} - dev_db.rs:54 - set demo1 pwd", "FOR DEV ONLY");
+
+  // update pwd for admin
+  let admin_user: User = UserBmc::first_by_username(&ctx, &mm, "admin").await?.unwrap();
+  UserBmc::update_pwd(&ctx, &mm, admin_user.id, DEMO_PWD).await?;
+  info!("{:<12} - dev_db.rs - set admin pwd", "FOR DEV ONLY");
   
 	Ok(())
 }
@@ -63,8 +85,7 @@ async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
 	// --- Read the file
 	let content = fs::read_to_string(file)?;
 
-	// FIXME: Make the split more sql proof.
-	let sqls: Vec<&str> = content.split(';').collect();
+	let sqls = migration::split_sql(&content);
 
 	for sql in sqls {
 		sqlx::query(sql).execute(db).await?;
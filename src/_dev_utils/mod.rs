@@ -16,8 +16,9 @@ mod dev_db;
 
 // end-region:      --- Modules
 
-/// Initialize environment for local development
-/// (for early development, will be called from main())
+/// Initialize environment for local development (recreates the db).
+///
+/// Panics when not in the dev/test `config::RunMode`.
 pub async fn init_dev() {
 	static INIT: OnceCell<()> = OnceCell::const_new();
 
@@ -28,18 +29,15 @@ pub async fn init_dev() {
 	.await;
 }
 
+/// Initialize the dev db once, and returns a new ModelManager (pool).
+///
+/// Note: A pool per test, as each `#[tokio::test]` has its own runtime, and a
+///       pool must not outlive the runtime its connections were opened on.
 pub async fn init_test() -> ModelManager {
-	static INIT: OnceCell<ModelManager> = OnceCell::const_new();
+	info!("{:<12} - mod.rs:27 - test_init_mm()", "FOR TEST ONLY");
+	init_dev().await;
 
-	let mm = INIT
-		.get_or_init(|| async {
-			info!("{:<12} - mod.rs:27 - test_init_mm()", "FOR TEST ONLY");
-			init_dev().await;
-			ModelManager::new().await.unwrap()
-		})
-		.await;
-
-	mm.clone()
+	ModelManager::new().await.unwrap()
 }
 
 pub async fn seed_tasks(
@@ -55,6 +53,7 @@ pub async fn seed_tasks(
 			mm,
 			TaskForCreate {
 				title: title.to_string(),
+				..Default::default()
 			},
 		)
 		.await?;
@@ -1,12 +1,14 @@
 use tracing::debug;
 
+use crate::log::sink::LogSinkKind;
 use crate::{Error, Result};
-use std::{env, str::FromStr, sync::OnceLock};
+use hyper::Uri;
+use std::{env, fs, net::IpAddr, str::FromStr, sync::OnceLock};
 
 pub fn config() -> &'static Config {
   static INSTANCE: OnceLock<Config> = OnceLock::new();
   INSTANCE.get_or_init(|| {
-    Config::load_from_env().unwrap_or_else(|ex| {
+    Config::load().unwrap_or_else(|ex| {
       panic!("FATAL - WHILE LOADING CONF : Cause {ex:?}");
     })
   })
@@ -14,6 +16,12 @@ pub fn config() -> &'static Config {
 
 #[allow(non_snake_case)]
 pub struct Config {
+	// -- Service
+	pub RUN_MODE: RunMode,
+	pub HOST: IpAddr,
+	pub PORT: u16,
+	/// On shutdown, how long the in-flight requests have to finish.
+	pub SHUTDOWN_TIMEOUT_SEC: u64,
   // -- Crypt
   pub PWD_KEY: Vec<u8>,
   pub TOKEN_KEY: Vec<u8>,
@@ -22,28 +30,412 @@ pub struct Config {
 	pub WEB_FOLDER: String,
   // -- Db
 	pub DB_URL: String,
+	pub DB_MIN_CONNECTIONS: u32,
+	pub DB_MAX_CONNECTIONS: u32,
+	pub DB_ACQUIRE_TIMEOUT_MS: u64,
+	/// 0 for no idle timeout (same for the max lifetime and statement timeout).
+	pub DB_IDLE_TIMEOUT_SEC: u64,
+	pub DB_MAX_LIFETIME_SEC: u64,
+	pub DB_STATEMENT_TIMEOUT_MS: u64,
+	/// On startup, the connection attempts while the db is not reachable
+	/// (the delay between them doubling from `DB_CONNECT_RETRY_DELAY_MS`).
+	pub DB_CONNECT_ATTEMPTS: u32,
+	pub DB_CONNECT_RETRY_DELAY_MS: u64,
+	// -- Request log (see `log::sink`)
+	pub LOG_SINKS: Vec<LogSinkKind>,
+	pub LOG_CHANNEL_CAPACITY: usize,
+	pub LOG_BATCH_SIZE: usize,
+	pub LOG_FLUSH_INTERVAL_MS: u64,
+	pub LOG_FILE_PATH: String,
+	pub LOG_FILE_MAX_BYTES: u64,
+	pub LOG_FILE_MAX_FILES: u32,
+	/// Required with the http sink.
+	pub LOG_HTTP_URL: Option<Uri>,
+	// -- Tracing (see `trace`)
+	/// The otlp/http collector base url (e.g., `http://localhost:4318`), the
+	/// spans are only exported when set.
+	pub OTLP_ENDPOINT: Option<String>,
+	pub OTLP_SERVICE_NAME: String,
 }
 
+/// The config values are resolved, by priority, from:
+/// - the env var (e.g., `SERVICE_DB_URL`),
+/// - the file of the `_FILE` env var (e.g., `SERVICE_DB_URL_FILE`, for the
+///   container secret mounts),
+/// - the key of the `SERVICE_CONFIG_FILE` toml file, without the `SERVICE_`
+///   prefix (e.g., `db_url`, or `db_url_file`),
+/// - the default, for the optional values.
 impl Config {
-	fn load_from_env() -> Result<Self> {
-		Ok(Self {
-      PWD_KEY: get_env_b64u_as_u8s("SERVICE_PWD_KEY")?,
-      TOKEN_KEY: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
-      TOKEN_DURATION: get_env_parse("SERVICE_TOKEN_DURATION_SEC")?,
-			WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
-			DB_URL: get_env("SERVICE_DB_URL")?,
-		})
+	fn load() -> Result<Self> {
+		let mut loader =
+			Loader::new(toml::Table::new(), |name| env::var(name).ok());
+		if let Ok(path) = env::var("SERVICE_CONFIG_FILE") {
+			loader.read_file(&path);
+		}
+		let config = Self::load_from(&mut loader);
+
+		match loader.problems.is_empty() {
+			true => Ok(config),
+			false => Err(Error::ConfigInvalid(loader.problems)),
+		}
+	}
+
+	/// Note: The values in `loader.problems` are defaulted.
+	fn load_from<F>(loader: &mut Loader<F>) -> Self
+	where
+		F: Fn(&str) -> Option<String>,
+	{
+		let config = Self {
+			RUN_MODE: loader.get_parse_or("SERVICE_RUN_MODE", RunMode::Prod),
+			HOST: loader.get_parse_or("SERVICE_HOST", IpAddr::from([127, 0, 0, 1])),
+			PORT: loader.get_parse_or("SERVICE_PORT", 8080),
+			SHUTDOWN_TIMEOUT_SEC: loader
+				.get_parse_or("SERVICE_SHUTDOWN_TIMEOUT_SEC", 30),
+			PWD_KEY: loader.get_b64u_as_u8s("SERVICE_PWD_KEY"),
+			TOKEN_KEY: loader.get_b64u_as_u8s("SERVICE_TOKEN_KEY"),
+			TOKEN_DURATION: loader.get_parse_or("SERVICE_TOKEN_DURATION_SEC", 1800.),
+			WEB_FOLDER: loader
+				.get_parse_or("SERVICE_WEB_FOLDER", "web-folder/".to_string()),
+			DB_URL: loader.get("SERVICE_DB_URL").unwrap_or_default(),
+			DB_MIN_CONNECTIONS: loader.get_parse_or("SERVICE_DB_MIN_CONNECTIONS", 0),
+			DB_MAX_CONNECTIONS: loader.get_parse_or("SERVICE_DB_MAX_CONNECTIONS", 5),
+			DB_ACQUIRE_TIMEOUT_MS: loader
+				.get_parse_or("SERVICE_DB_ACQUIRE_TIMEOUT_MS", 3000),
+			DB_IDLE_TIMEOUT_SEC: loader
+				.get_parse_or("SERVICE_DB_IDLE_TIMEOUT_SEC", 600),
+			DB_MAX_LIFETIME_SEC: loader
+				.get_parse_or("SERVICE_DB_MAX_LIFETIME_SEC", 1800),
+			DB_STATEMENT_TIMEOUT_MS: loader
+				.get_parse_or("SERVICE_DB_STATEMENT_TIMEOUT_MS", 0),
+			DB_CONNECT_ATTEMPTS: loader
+				.get_parse_or("SERVICE_DB_CONNECT_ATTEMPTS", 5),
+			DB_CONNECT_RETRY_DELAY_MS: loader
+				.get_parse_or("SERVICE_DB_CONNECT_RETRY_DELAY_MS", 500),
+			LOG_SINKS: loader.get_list_or("SERVICE_LOG_SINKS", Vec::new()),
+			LOG_CHANNEL_CAPACITY: loader
+				.get_parse_or("SERVICE_LOG_CHANNEL_CAPACITY", 1024),
+			LOG_BATCH_SIZE: loader.get_parse_or("SERVICE_LOG_BATCH_SIZE", 100),
+			LOG_FLUSH_INTERVAL_MS: loader
+				.get_parse_or("SERVICE_LOG_FLUSH_INTERVAL_MS", 1000),
+			LOG_FILE_PATH: loader.get_parse_or(
+				"SERVICE_LOG_FILE_PATH",
+				"logs/requests.jsonl".to_string(),
+			),
+			LOG_FILE_MAX_BYTES: loader
+				.get_parse_or("SERVICE_LOG_FILE_MAX_BYTES", 10 * 1024 * 1024),
+			LOG_FILE_MAX_FILES: loader.get_parse_or("SERVICE_LOG_FILE_MAX_FILES", 5),
+			LOG_HTTP_URL: loader.get_parse_opt("SERVICE_LOG_HTTP_URL"),
+			OTLP_ENDPOINT: loader.get_parse_opt("SERVICE_OTLP_ENDPOINT"),
+			OTLP_SERVICE_NAME: loader.get_parse_or(
+				"SERVICE_OTLP_SERVICE_NAME",
+				env!("CARGO_PKG_NAME").to_string(),
+			),
+		};
+
+		// -- Cross checks
+		if config.LOG_SINKS.contains(&LogSinkKind::Http)
+			&& config.LOG_HTTP_URL.is_none()
+			&& !loader.has_problem("SERVICE_LOG_HTTP_URL")
+		{
+			loader
+				.problems
+				.push(Error::ConfigMissingEnv("SERVICE_LOG_HTTP_URL"));
+		}
+
+		config
+	}
+}
+
+/// The runtime mode (`SERVICE_RUN_MODE`: dev, test or prod).
+///
+/// Prod when not set, so a deployed binary never runs the dev only code
+/// (e.g., the dev db recreation of `_dev_utils`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunMode {
+	Dev,
+	Test,
+	Prod,
+}
+
+impl RunMode {
+	/// Can the db be recreated and seeded (see `_dev_utils::init_dev`).
+	pub fn allows_dev_db(&self) -> bool {
+		matches!(self, RunMode::Dev | RunMode::Test)
 	}
 }
 
-fn get_env(name: &'static str) -> Result<String> {
-	env::var(name).map_err(|err| Error::ConfigMissingEnv(name))
+impl FromStr for RunMode {
+	type Err = ();
+
+	fn from_str(val: &str) -> core::result::Result<Self, ()> {
+		match val {
+			"dev" => Ok(RunMode::Dev),
+			"test" => Ok(RunMode::Test),
+			"prod" => Ok(RunMode::Prod),
+			_ => Err(()),
+		}
+	}
 }
 
-fn get_env_b64u_as_u8s(name: &'static str) -> Result<Vec<u8>> {
-  base64_url::decode(&get_env(name)?).map_err(|err| Error::ConfigWrongFormat(name))
+// region:    --- Loader
+
+/// Resolves the config values, collecting all the problems.
+struct Loader<F> {
+	file: toml::Table,
+	get_env: F,
+	problems: Vec<Error>,
 }
 
-fn get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
-  get_env(name)?.parse().map_err(|err| Error::ConfigWrongFormat(name))
+impl<F> Loader<F>
+where
+	F: Fn(&str) -> Option<String>,
+{
+	fn new(file: toml::Table, get_env: F) -> Self {
+		Self {
+			file,
+			get_env,
+			problems: Vec::new(),
+		}
+	}
+
+	/// Reads the `SERVICE_CONFIG_FILE` toml file (a problem if it cannot be
+	/// read or parsed, the values are then resolved without it).
+	fn read_file(&mut self, path: &str) {
+		let file = fs::read_to_string(path)
+			.map_err(|err| err.to_string())
+			.and_then(|content| {
+				content
+					.parse::<toml::Table>()
+					.map_err(|err| err.to_string())
+			});
+		match file {
+			Ok(file) => self.file = file,
+			Err(cause) => self.problems.push(Error::ConfigReadFile {
+				name: "SERVICE_CONFIG_FILE",
+				path: path.to_string(),
+				cause,
+			}),
+		}
+	}
+
+	/// The value of `name`, none if not set anywhere (see `Config`).
+	///
+	/// Note: A toml array is as a comma separated list (see `get_list_or`).
+	fn get_opt(&mut self, name: &'static str) -> Option<String> {
+		if let Some(val) = (self.get_env)(name) {
+			return Some(val);
+		}
+		let name_file = format!("{name}_FILE");
+		if let Some(path) = (self.get_env)(&name_file) {
+			return self.read_secret(name, &path);
+		}
+
+		let key = name.trim_start_matches("SERVICE_").to_lowercase();
+		match self.file.get(&key) {
+			Some(toml::Value::Array(items)) => {
+				let items: Vec<String> = items.iter().map(toml_to_string).collect();
+				Some(items.join(","))
+			}
+			Some(val) => Some(toml_to_string(val)),
+			None => match self.file.get(&format!("{key}_file")) {
+				Some(toml::Value::String(path)) => {
+					let path = path.clone();
+					self.read_secret(name, &path)
+				}
+				Some(_) => {
+					self.problems.push(Error::ConfigWrongFormat(name));
+					None
+				}
+				None => None,
+			},
+		}
+	}
+
+	fn get(&mut self, name: &'static str) -> Option<String> {
+		let val = self.get_opt(name);
+		if val.is_none() && !self.has_problem(name) {
+			self.problems.push(Error::ConfigMissingEnv(name));
+		}
+		val
+	}
+
+	fn get_parse_or<T: FromStr>(&mut self, name: &'static str, default: T) -> T {
+		self.get_parse_opt(name).unwrap_or(default)
+	}
+
+	fn get_parse_opt<T: FromStr>(&mut self, name: &'static str) -> Option<T> {
+		match self.get_opt(name).map(|val| val.parse()) {
+			Some(Ok(val)) => Some(val),
+			Some(Err(_)) => {
+				self.problems.push(Error::ConfigWrongFormat(name));
+				None
+			}
+			None => None,
+		}
+	}
+
+	/// A comma separated list (e.g., "stdout, file").
+	fn get_list_or<T: FromStr>(
+		&mut self,
+		name: &'static str,
+		default: Vec<T>,
+	) -> Vec<T> {
+		let Some(val) = self.get_opt(name) else {
+			return default;
+		};
+		let list: core::result::Result<Vec<T>, _> = val
+			.split(',')
+			.map(str::trim)
+			.filter(|item| !item.is_empty())
+			.map(str::parse)
+			.collect();
+		list.unwrap_or_else(|_| {
+			self.problems.push(Error::ConfigWrongFormat(name));
+			default
+		})
+	}
+
+	fn get_b64u_as_u8s(&mut self, name: &'static str) -> Vec<u8> {
+		let Some(val) = self.get(name) else {
+			return Vec::new();
+		};
+		base64_url::decode(&val).unwrap_or_else(|_| {
+			self.problems.push(Error::ConfigWrongFormat(name));
+			Vec::new()
+		})
+	}
+
+	/// Note: Trimmed, as secret files usually end with a new line.
+	fn read_secret(&mut self, name: &'static str, path: &str) -> Option<String> {
+		match fs::read_to_string(path) {
+			Ok(val) => Some(val.trim().to_string()),
+			Err(err) => {
+				self.problems.push(Error::ConfigReadFile {
+					name,
+					path: path.to_string(),
+					cause: err.to_string(),
+				});
+				None
+			}
+		}
+	}
+
+	fn has_problem(&self, name: &'static str) -> bool {
+		self.problems.iter().any(|problem| match problem {
+			Error::ConfigReadFile { name: n, .. } => *n == name,
+			Error::ConfigWrongFormat(n) => *n == name,
+			_ => false,
+		})
+	}
+}
+
+/// The strings without their toml quotes.
+fn toml_to_string(val: &toml::Value) -> String {
+	match val {
+		toml::Value::String(val) => val.clone(),
+		val => val.to_string(),
+	}
+}
+
+// endregion: --- Loader
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	#[test]
+	fn test_config_load_priorities_and_defaults() -> anyhow::Result<()> {
+		let file: toml::Table = r#"
+			port = 9000
+			db_url = "postgres://file"
+			web_folder = "file-folder/"
+		"#
+		.parse()?;
+		let env = HashMap::from([
+			("SERVICE_PWD_KEY", "AAAA"),
+			("SERVICE_TOKEN_KEY", "AQID"),
+			("SERVICE_WEB_FOLDER", "env-folder/"),
+		]);
+		let mut loader =
+			Loader::new(file, |name| env.get(name).map(|v| v.to_string()));
+
+		let config = Config::load_from(&mut loader);
+
+		assert!(loader.problems.is_empty(), "{:?}", loader.problems);
+		assert_eq!(config.PORT, 9000);
+		assert_eq!(config.DB_URL, "postgres://file");
+		assert_eq!(config.WEB_FOLDER, "env-folder/");
+		assert_eq!(config.TOKEN_KEY, [1, 2, 3]);
+		assert_eq!(config.RUN_MODE, RunMode::Prod);
+		assert_eq!(config.TOKEN_DURATION, 1800.);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_config_load_reports_all_problems() -> anyhow::Result<()> {
+		let env = HashMap::from([
+			("SERVICE_PORT", "not-a-port"),
+			("SERVICE_TOKEN_KEY", "not base64 !"),
+			("SERVICE_DB_URL_FILE", "/no/such/secret"),
+			("SERVICE_LOG_SINKS", "stdout, http"),
+		]);
+		let mut loader = Loader::new(toml::Table::new(), |name| {
+			env.get(name).map(|v| v.to_string())
+		});
+
+		Config::load_from(&mut loader);
+
+		let problems: Vec<String> =
+			loader.problems.iter().map(|p| format!("{p:?}")).collect();
+		assert_eq!(problems.len(), 5, "{problems:?}");
+		assert!(problems[0].contains("ConfigWrongFormat(\"SERVICE_PORT\")"));
+		assert!(problems[1].contains("ConfigMissingEnv(\"SERVICE_PWD_KEY\")"));
+		assert!(problems[2].contains("ConfigWrongFormat(\"SERVICE_TOKEN_KEY\")"));
+		assert!(problems[3].contains("ConfigReadFile { name: \"SERVICE_DB_URL\""));
+		assert!(problems[4].contains("ConfigMissingEnv(\"SERVICE_LOG_HTTP_URL\")"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_config_load_file_problem_with_the_others() -> anyhow::Result<()> {
+		let mut loader = Loader::new(toml::Table::new(), |_| None);
+
+		loader.read_file("/no/such/config.toml");
+		Config::load_from(&mut loader);
+
+		let problems: Vec<String> =
+			loader.problems.iter().map(|p| format!("{p:?}")).collect();
+		assert!(
+			problems[0].contains("ConfigReadFile { name: \"SERVICE_CONFIG_FILE\""),
+			"{problems:?}"
+		);
+		assert!(
+			problems.iter().any(|p| p.contains("\"SERVICE_PWD_KEY\"")),
+			"other problems reported {problems:?}"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_config_load_file_arrays_as_lists() -> anyhow::Result<()> {
+		let file: toml::Table = r#"
+			pwd_key = "AAAA"
+			token_key = "AQID"
+			db_url = "postgres://file"
+			log_sinks = ["stdout", "file"]
+		"#
+		.parse()?;
+		let mut loader = Loader::new(file, |_| None);
+
+		let config = Config::load_from(&mut loader);
+
+		assert!(loader.problems.is_empty(), "{:?}", loader.problems);
+		assert_eq!(config.LOG_SINKS, [LogSinkKind::Stdout, LogSinkKind::File]);
+
+		Ok(())
+	}
 }
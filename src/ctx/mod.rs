@@ -9,21 +9,34 @@ pub use self::error::{Error, Result};
 #[derive(Clone, Debug)]
 pub struct Ctx {
 	user_id: i64,
+	admin: bool,
 }
 
 // Constructor.
 impl Ctx {
 	pub fn root_ctx() -> Self {
-		Ctx { user_id: 0 }
+		Ctx {
+			user_id: 0,
+			admin: true,
+		}
 	}
 
 	pub fn new(user_id: i64) -> Result<Self> {
 		if user_id == 0 {
 			Err(Error::CtxCannotNewRootCtx)
 		} else {
-			Ok(Self { user_id })
+			Ok(Self {
+				user_id,
+				admin: false,
+			})
 		}
 	}
+
+	pub fn new_admin(user_id: i64) -> Result<Self> {
+		let mut ctx = Self::new(user_id)?;
+		ctx.admin = true;
+		Ok(ctx)
+	}
 }
 
 // Property Accessors.
@@ -31,4 +44,8 @@ impl Ctx {
 	pub fn user_id(&self) -> i64 {
 		self.user_id
 	}
+
+	pub fn is_admin(&self) -> bool {
+		self.admin
+	}
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::model;
+use crate::{metric, model, trace};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -6,8 +6,25 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
 	// -- Modules
 	Model(model::Error),
+	Metric(metric::Error),
+	Trace(trace::Error),
   ConfigMissingEnv(&'static str),
   ConfigWrongFormat(&'static str),
+	ConfigReadFile {
+		name: &'static str,
+		path: String,
+		cause: String,
+	},
+	/// All the problems of the config (see `config::Config`).
+	ConfigInvalid(Vec<Error>),
+	/// The dev db recreation (`_dev_utils`) refused to run.
+	DevDbRefused {
+		reason: &'static str,
+	},
+	// -- Server
+	Server(hyper::Error),
+	/// The server task panicked (or was aborted).
+	ServerTask(tokio::task::JoinError),
 }
 
 // region:    --- Froms
@@ -16,6 +33,18 @@ impl From<model::Error> for Error {
 		Self::Model(val)
 	}
 }
+
+impl From<metric::Error> for Error {
+	fn from(val: metric::Error) -> Self {
+		Self::Metric(val)
+	}
+}
+
+impl From<trace::Error> for Error {
+	fn from(val: trace::Error) -> Self {
+		Self::Trace(val)
+	}
+}
 // endregion: --- Froms
 
 // region:    --- Error Boilerplate
@@ -1,29 +1,118 @@
+// region:    --- Modules
+
+pub mod sink;
+
+use crate::config::Config;
 use crate::ctx::Ctx;
+use crate::metric;
 use crate::rpc::RpcInfo;
+use crate::utils::format_time;
 use crate::web::{self, ClientError};
 use crate::Result;
-use axum::http::{Method, Uri};
+use axum::http::{Method, StatusCode, Uri};
 use serde::Serialize;
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::debug;
-use uuid::Uuid;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+use self::sink::{FileSink, HttpSink, LogMsg, LogSink, LogSinkKind, StdoutSink};
+
+// endregion: --- Modules
+
+/// The channel of the sinks worker (see `init_sinks`).
+static LOG_TX: OnceLock<mpsc::Sender<LogMsg>> = OnceLock::new();
+
+/// How long `flush` waits for the sinks.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starts the worker writing the request log lines to the `LOG_SINKS`
+/// (without sinks, the lines are only traced).
+pub fn init_sinks(config: &Config) {
+	let sinks: Vec<Box<dyn LogSink>> = config
+		.LOG_SINKS
+		.iter()
+		.filter_map(|kind| -> Option<Box<dyn LogSink>> {
+			match kind {
+				LogSinkKind::Stdout => Some(Box::new(StdoutSink)),
+				LogSinkKind::File => Some(Box::new(FileSink::new(
+					&config.LOG_FILE_PATH,
+					config.LOG_FILE_MAX_BYTES,
+					config.LOG_FILE_MAX_FILES,
+				))),
+				// Note: The config requires the url with the http sink.
+				LogSinkKind::Http => config
+					.LOG_HTTP_URL
+					.clone()
+					.map(|url| Box::new(HttpSink::new(url)) as Box<dyn LogSink>),
+			}
+		})
+		.collect();
+	if sinks.is_empty() {
+		return;
+	}
+
+	let tx = sink::spawn_worker(
+		sinks,
+		sink::WorkerOptions {
+			capacity: config.LOG_CHANNEL_CAPACITY,
+			batch_size: config.LOG_BATCH_SIZE,
+			flush_interval: Duration::from_millis(config.LOG_FLUSH_INTERVAL_MS),
+		},
+	);
+	let _ = LOG_TX.set(tx);
+}
+
+/// Writes the pending request log lines to the sinks (e.g., on shutdown).
+pub async fn flush() {
+	let Some(tx) = LOG_TX.get() else {
+		return;
+	};
+	let (done_tx, done_rx) = oneshot::channel();
+	let flushed = async {
+		tx.send(LogMsg::Flush(done_tx)).await.ok()?;
+		done_rx.await.ok()
+	};
+	if tokio::time::timeout(FLUSH_TIMEOUT, flushed).await.is_err() {
+		warn!("{:<12} - flush timed out", "REQUEST LOG");
+	}
+}
+
+/// Note: Never blocks, the line is dropped when the sinks are behind.
+fn send(log_line: RequestLogLine) {
+	if let Some(tx) = LOG_TX.get() {
+		if tx.try_send(LogMsg::Line(Box::new(log_line))).is_err() {
+			metric::record_log_dropped();
+		}
+	}
+}
+
+/// The http attributes of a request, and of its response.
+pub struct HttpInfo {
+	pub method: Method,
+	pub uri: Uri,
+	pub status: StatusCode,
+	/// When the request came in.
+	pub time_in: OffsetDateTime,
+	pub duration: Duration,
+	pub client_addr: Option<SocketAddr>,
+	pub user_agent: Option<String>,
+	/// The response body size, when known.
+	pub res_size: Option<u64>,
+}
 
 pub async fn log_request(
-	uuid: Uuid,
-	req_method: Method,
-	uri: Uri,
+	req_id: &str,
+	http: HttpInfo,
 	rpc_info: Option<&RpcInfo>,
 	ctx: Option<Ctx>,
 	web_error: Option<&web::Error>,
 	client_error: Option<ClientError>,
 ) -> Result<()> {
-	let timestamp = SystemTime::now()
-		.duration_since(UNIX_EPOCH)
-		.unwrap()
-		.as_millis();
-
 	let error_type = web_error.map(|se| se.as_ref().to_string());
 	let error_data = serde_json::to_value(web_error)
 		.ok()
@@ -31,11 +120,17 @@ pub async fn log_request(
 
 	// Create the RequestLogLine
 	let log_line = RequestLogLine {
-		uuid: uuid.to_string(),
-		timestamp: timestamp.to_string(),
+		uuid: req_id.to_string(),
+		timestamp: format_time(http.time_in),
+		duration_ms: http.duration.as_secs_f64() * 1000.,
+
+		http_path: http.uri.to_string(),
+		http_method: http.method.to_string(),
+		http_status: http.status.as_u16(),
+		http_res_size: http.res_size,
 
-		http_path: uri.to_string(),
-		http_method: req_method.to_string(),
+		client_ip: http.client_addr.map(|addr| addr.ip().to_string()),
+		user_agent: http.user_agent,
 
 		user_id: ctx.map(|c| c.user_id()),
 
@@ -50,16 +145,17 @@ pub async fn log_request(
 
 	debug!(" REQUEST LOG LINE:\n{}", json!(log_line));
 
-	// TODO - Send to cloud-watch.
+	send(log_line);
 
 	Ok(())
 }
 
 #[skip_serializing_none]
 #[derive(Serialize)]
-struct RequestLogLine {
-	uuid: String,      // uuid string formatted
-	timestamp: String, // (should be iso8601)
+pub struct RequestLogLine {
+	uuid: String,      // the request id (see `web::mw_req_stamp`)
+	timestamp: String, // (Rfc3339) when the request came in
+	duration_ms: f64,
 
 	// -- User and context attributes.
 	user_id: Option<i64>,
@@ -71,6 +167,12 @@ struct RequestLogLine {
 	// -- http request attributes.
 	http_path: String,
 	http_method: String,
+	http_status: u16,
+	http_res_size: Option<u64>,
+
+	// -- client attributes.
+	client_ip: Option<String>,
+	user_agent: Option<String>,
 
 	// -- Errors attributes.
 	client_error_type: Option<String>,
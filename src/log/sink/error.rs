@@ -0,0 +1,54 @@
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+	HttpStatus { status: u16 },
+	HttpTimeout,
+
+	// -- Externals
+	Io(#[serde_as(as = "DisplayFromStr")] std::io::Error),
+	SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
+	Hyper(#[serde_as(as = "DisplayFromStr")] hyper::Error),
+	Http(#[serde_as(as = "DisplayFromStr")] hyper::http::Error),
+}
+
+impl From<std::io::Error> for Error {
+	fn from(val: std::io::Error) -> Self {
+		Self::Io(val)
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(val: serde_json::Error) -> Self {
+		Self::SerdeJson(val)
+	}
+}
+
+impl From<hyper::Error> for Error {
+	fn from(val: hyper::Error) -> Self {
+		Self::Hyper(val)
+	}
+}
+
+impl From<hyper::http::Error> for Error {
+	fn from(val: hyper::http::Error) -> Self {
+		Self::Http(val)
+	}
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
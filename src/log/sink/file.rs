@@ -0,0 +1,145 @@
+use super::{LogSink, Result};
+use crate::log::RequestLogLine;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// Appends the lines as json lines to `path`, rotating it when it would
+/// exceed `max_bytes` (`path` to `path.1`, `path.1` to `path.2`, ...,
+/// keeping `max_files` rotated files).
+pub struct FileSink {
+	path: PathBuf,
+	max_bytes: u64,
+	max_files: u32,
+	/// The open file, with its size.
+	file: Option<(File, u64)>,
+}
+
+impl FileSink {
+	pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_files: u32) -> Self {
+		Self {
+			path: path.into(),
+			max_bytes,
+			max_files,
+			file: None,
+		}
+	}
+
+	async fn open(&self) -> Result<(File, u64)> {
+		if let Some(dir) = self.path.parent() {
+			fs::create_dir_all(dir).await?;
+		}
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.await?;
+		let size = file.metadata().await?.len();
+
+		Ok((file, size))
+	}
+
+	async fn rotate(&mut self) -> Result<()> {
+		self.file = None;
+		if self.max_files == 0 {
+			fs::remove_file(&self.path).await?;
+			return Ok(());
+		}
+
+		// shift the rotated files, the oldest is overwritten
+		for n in (1..self.max_files).rev() {
+			let from = self.rotated_path(n);
+			if fs::try_exists(&from).await? {
+				fs::rename(&from, self.rotated_path(n + 1)).await?;
+			}
+		}
+		fs::rename(&self.path, self.rotated_path(1)).await?;
+
+		Ok(())
+	}
+
+	fn rotated_path(&self, n: u32) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{n}"));
+		path.into()
+	}
+}
+
+#[async_trait]
+impl LogSink for FileSink {
+	fn name(&self) -> &'static str {
+		"file"
+	}
+
+	async fn write(&mut self, lines: &[RequestLogLine]) -> Result<()> {
+		let mut buf = Vec::new();
+		for line in lines {
+			serde_json::to_writer(&mut buf, line)?;
+			buf.push(b'\n');
+		}
+
+		let size = match &self.file {
+			Some((_, size)) => *size,
+			None => {
+				self.file = Some(self.open().await?);
+				self.file.as_ref().map_or(0, |(_, size)| *size)
+			}
+		};
+		if size > 0 && size + buf.len() as u64 > self.max_bytes {
+			self.rotate().await?;
+			self.file = Some(self.open().await?);
+		}
+
+		if let Some((file, size)) = self.file.as_mut() {
+			file.write_all(&buf).await?;
+			file.flush().await?;
+			*size += buf.len() as u64;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::log::sink::tests::new_test_line;
+	use anyhow::Result;
+
+	#[tokio::test]
+	async fn test_log_sink_file_rotation() -> Result<()> {
+		let dir = std::env::temp_dir().join(format!(
+			"test_log_sink_file_rotation-{}",
+			uuid::Uuid::new_v4()
+		));
+		let path = dir.join("requests.jsonl");
+		let line_len = serde_json::to_vec(&new_test_line("a"))?.len() as u64 + 1;
+		// 2 lines per file, 2 rotated files kept
+		let mut sink = FileSink::new(&path, line_len * 2, 2);
+
+		// -- Exec
+		for uuid in ["a", "b", "c", "d", "e", "f", "g"] {
+			sink.write(&[new_test_line(uuid)]).await?;
+		}
+
+		// -- Check
+		let uuids_of = |path: PathBuf| -> Result<Vec<String>> {
+			let mut uuids = Vec::new();
+			for line in std::fs::read_to_string(path)?.lines() {
+				let line: serde_json::Value = serde_json::from_str(line)?;
+				uuids.push(line["uuid"].as_str().unwrap_or_default().to_string());
+			}
+			Ok(uuids)
+		};
+		assert_eq!(uuids_of(path.clone())?, ["g"]);
+		assert_eq!(uuids_of(sink.rotated_path(1))?, ["e", "f"]);
+		assert_eq!(uuids_of(sink.rotated_path(2))?, ["c", "d"]);
+		assert!(!sink.rotated_path(3).exists(), "only 2 rotated files");
+
+		// -- Clean
+		std::fs::remove_dir_all(dir)?;
+
+		Ok(())
+	}
+}
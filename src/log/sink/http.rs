@@ -0,0 +1,123 @@
+use super::{Error, LogSink, Result};
+use crate::log::RequestLogLine;
+use async_trait::async_trait;
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Method, Request, Uri};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Posts each batch of lines, as a json array, to `url` (e.g., a log
+/// shipper agent), a batch failing being dropped.
+///
+/// Note: http only, the shipper being expected to run next to the service.
+pub struct HttpSink {
+	client: Client<HttpConnector>,
+	url: Uri,
+}
+
+impl HttpSink {
+	pub fn new(url: Uri) -> Self {
+		Self {
+			client: Client::new(),
+			url,
+		}
+	}
+}
+
+#[async_trait]
+impl LogSink for HttpSink {
+	fn name(&self) -> &'static str {
+		"http"
+	}
+
+	async fn write(&mut self, lines: &[RequestLogLine]) -> Result<()> {
+		let body = serde_json::to_vec(lines)?;
+		let req = Request::builder()
+			.method(Method::POST)
+			.uri(self.url.clone())
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body))?;
+
+		let res = tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(req))
+			.await
+			.map_err(|_| Error::HttpTimeout)??;
+		if !res.status().is_success() {
+			return Err(Error::HttpStatus {
+				status: res.status().as_u16(),
+			});
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::log::sink::tests::new_test_line;
+	use anyhow::Result;
+	use axum::extract::State;
+	use axum::http::StatusCode;
+	use axum::routing::post;
+	use axum::{Json, Router};
+	use serde_json::Value;
+	use std::net::SocketAddr;
+	use std::sync::{Arc, Mutex};
+
+	type Received = Arc<Mutex<Vec<Value>>>;
+
+	/// A local log shipper stand-in, failing when `fail` is in the batch.
+	async fn spawn_mock_shipper() -> Result<(Uri, Received)> {
+		let received: Received = Arc::default();
+		let app = Router::new()
+			.route(
+				"/logs",
+				post(
+					|State(received): State<Received>, Json(batch): Json<Value>| async move {
+						if batch.to_string().contains("fail") {
+							return StatusCode::SERVICE_UNAVAILABLE;
+						}
+						received.lock().unwrap().push(batch);
+						StatusCode::OK
+					},
+				),
+			)
+			.with_state(received.clone());
+		let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+			.serve(app.into_make_service());
+		let url = format!("http://{}/logs", server.local_addr()).parse()?;
+		tokio::spawn(server);
+
+		Ok((url, received))
+	}
+
+	#[tokio::test]
+	async fn test_log_sink_http_batch() -> Result<()> {
+		let (url, received) = spawn_mock_shipper().await?;
+		let mut sink = HttpSink::new(url);
+
+		// -- Exec
+		sink.write(&[new_test_line("a"), new_test_line("b")])
+			.await?;
+		let res = sink.write(&[new_test_line("fail")]).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::HttpStatus { status: 503 })),
+			"{res:?}"
+		);
+		let received = received.lock().unwrap().clone();
+		assert_eq!(received.len(), 1);
+		let uuids: Vec<&str> = received[0]
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|line| line["uuid"].as_str())
+			.collect();
+		assert_eq!(uuids, ["a", "b"]);
+
+		Ok(())
+	}
+}
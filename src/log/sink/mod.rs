@@ -0,0 +1,232 @@
+//! The destinations of the `RequestLogLine`s (see `LogSink`).
+//!
+//! The lines are sent through a bounded channel to a worker task writing
+//! them by batch to the sinks, so logging never blocks or fails a request:
+//! when the channel is full the line is dropped (and counted), and the sink
+//! errors are only traced.
+
+// region:    --- Modules
+
+mod error;
+mod file;
+mod http;
+mod stdout;
+
+pub use self::error::{Error, Result};
+pub use self::file::FileSink;
+pub use self::http::HttpSink;
+pub use self::stdout::StdoutSink;
+
+use super::RequestLogLine;
+use crate::metric;
+use async_trait::async_trait;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{self, MissedTickBehavior};
+use tracing::warn;
+
+// endregion: --- Modules
+
+#[async_trait]
+pub trait LogSink: Send {
+	fn name(&self) -> &'static str;
+
+	/// Writes the `lines` batch, in order.
+	async fn write(&mut self, lines: &[RequestLogLine]) -> Result<()>;
+}
+
+/// The kinds of sinks of the config (`SERVICE_LOG_SINKS`, e.g. "stdout,file").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogSinkKind {
+	Stdout,
+	File,
+	Http,
+}
+
+impl FromStr for LogSinkKind {
+	type Err = ();
+
+	fn from_str(val: &str) -> core::result::Result<Self, ()> {
+		match val {
+			"stdout" => Ok(LogSinkKind::Stdout),
+			"file" => Ok(LogSinkKind::File),
+			"http" => Ok(LogSinkKind::Http),
+			_ => Err(()),
+		}
+	}
+}
+
+pub enum LogMsg {
+	Line(Box<RequestLogLine>),
+	/// Writes the pending lines, then answers.
+	Flush(oneshot::Sender<()>),
+}
+
+pub struct WorkerOptions {
+	pub capacity: usize,
+	pub batch_size: usize,
+	/// The pending lines are written at least this often.
+	pub flush_interval: Duration,
+}
+
+/// Spawns the worker writing to the `sinks`, returns its channel.
+pub fn spawn_worker(
+	sinks: Vec<Box<dyn LogSink>>,
+	options: WorkerOptions,
+) -> mpsc::Sender<LogMsg> {
+	let (tx, rx) = mpsc::channel(options.capacity);
+	tokio::spawn(run_worker(rx, sinks, options));
+	tx
+}
+
+async fn run_worker(
+	mut rx: mpsc::Receiver<LogMsg>,
+	mut sinks: Vec<Box<dyn LogSink>>,
+	options: WorkerOptions,
+) {
+	let mut batch = Vec::with_capacity(options.batch_size);
+	// Note: An interval rather than a timeout on `recv`, which would restart
+	//       on every line and never fire under steady traffic.
+	let mut flush_interval = time::interval(options.flush_interval);
+	flush_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+	// the first tick completes immediately
+	flush_interval.tick().await;
+	loop {
+		let msg = tokio::select! {
+			msg = rx.recv() => Some(msg),
+			_ = flush_interval.tick() => None,
+		};
+		match msg {
+			Some(Some(LogMsg::Line(line))) => {
+				batch.push(*line);
+				if batch.len() >= options.batch_size {
+					write_batch(&mut sinks, &mut batch).await;
+				}
+			}
+			Some(Some(LogMsg::Flush(done))) => {
+				write_batch(&mut sinks, &mut batch).await;
+				let _ = done.send(());
+			}
+			// all the senders dropped
+			Some(None) => {
+				write_batch(&mut sinks, &mut batch).await;
+				break;
+			}
+			// flush interval
+			None => write_batch(&mut sinks, &mut batch).await,
+		}
+	}
+}
+
+async fn write_batch(
+	sinks: &mut [Box<dyn LogSink>],
+	batch: &mut Vec<RequestLogLine>,
+) {
+	if batch.is_empty() {
+		return;
+	}
+	for sink in sinks.iter_mut() {
+		if let Err(err) = sink.write(batch).await {
+			warn!("{:<12} - {} sink - {err:?}", "REQUEST LOG", sink.name());
+			metric::record_log_sink_error(sink.name());
+		}
+	}
+	batch.clear();
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+	use super::*;
+	use anyhow::Result;
+	use std::sync::{Arc, Mutex};
+
+	pub fn new_test_line(uuid: &str) -> RequestLogLine {
+		RequestLogLine {
+			uuid: uuid.to_string(),
+			timestamp: "2026-10-18T00:00:00Z".to_string(),
+			duration_ms: 1.5,
+			user_id: None,
+			rpc_id: None,
+			rpc_method: None,
+			http_path: "/api/rpc".to_string(),
+			http_method: "POST".to_string(),
+			http_status: 200,
+			http_res_size: None,
+			client_ip: None,
+			user_agent: None,
+			client_error_type: None,
+			error_type: None,
+			error_data: None,
+		}
+	}
+
+	/// Collects the batches written.
+	struct MockSink(Arc<Mutex<Vec<Vec<String>>>>);
+
+	#[async_trait]
+	impl LogSink for MockSink {
+		fn name(&self) -> &'static str {
+			"mock"
+		}
+
+		async fn write(&mut self, lines: &[RequestLogLine]) -> super::Result<()> {
+			let uuids = lines.iter().map(|l| l.uuid.clone()).collect();
+			self.0.lock().unwrap().push(uuids);
+			Ok(())
+		}
+	}
+
+	#[tokio::test]
+	async fn test_log_sink_worker_batches_and_flush() -> Result<()> {
+		let batches = Arc::new(Mutex::new(Vec::new()));
+		let tx = spawn_worker(
+			vec![Box::new(MockSink(batches.clone()))],
+			WorkerOptions {
+				capacity: 10,
+				batch_size: 2,
+				flush_interval: Duration::from_secs(60),
+			},
+		);
+
+		// -- Exec
+		for uuid in ["a", "b", "c"] {
+			tx.try_send(LogMsg::Line(Box::new(new_test_line(uuid))))?;
+		}
+		let (done_tx, done_rx) = oneshot::channel();
+		tx.send(LogMsg::Flush(done_tx)).await?;
+		done_rx.await?;
+
+		// -- Check
+		let batches = batches.lock().unwrap().clone();
+		assert_eq!(batches, [vec!["a", "b"], vec!["c"]]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_log_sink_worker_flush_interval_steady() -> Result<()> {
+		let batches = Arc::new(Mutex::new(Vec::new()));
+		let tx = spawn_worker(
+			vec![Box::new(MockSink(batches.clone()))],
+			WorkerOptions {
+				capacity: 10,
+				batch_size: 100,
+				flush_interval: Duration::from_millis(100),
+			},
+		);
+
+		// -- Exec (a line more often than the flush interval)
+		for uuid in ["a", "b", "c", "d", "e"] {
+			tx.try_send(LogMsg::Line(Box::new(new_test_line(uuid))))?;
+			time::sleep(Duration::from_millis(40)).await;
+		}
+
+		// -- Check (written by the interval, no flush and batch not full)
+		let batches = batches.lock().unwrap().clone();
+		assert!(!batches.is_empty(), "flushed by the interval");
+		assert_eq!(batches[0][0], "a");
+
+		Ok(())
+	}
+}
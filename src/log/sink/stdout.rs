@@ -0,0 +1,29 @@
+use super::{LogSink, Result};
+use crate::log::RequestLogLine;
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+
+/// Writes the lines as json lines to stdout (e.g., for a log collector
+/// reading the container output).
+pub struct StdoutSink;
+
+#[async_trait]
+impl LogSink for StdoutSink {
+	fn name(&self) -> &'static str {
+		"stdout"
+	}
+
+	async fn write(&mut self, lines: &[RequestLogLine]) -> Result<()> {
+		let mut buf = Vec::new();
+		for line in lines {
+			serde_json::to_writer(&mut buf, line)?;
+			buf.push(b'\n');
+		}
+
+		let mut stdout = tokio::io::stdout();
+		stdout.write_all(&buf).await?;
+		stdout.flush().await?;
+
+		Ok(())
+	}
+}
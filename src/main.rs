@@ -7,8 +7,10 @@ mod crypt;
 mod ctx;
 mod error;
 mod log;
+mod metric;
 mod model;
 mod rpc;
+mod trace;
 mod utils;
 mod web;
 
@@ -18,35 +20,50 @@ pub mod _dev_utils;
 pub use self::config::config;
 pub use self::error::{Error, Result};
 
+use crate::config::RunMode;
 use crate::model::ModelManager;
 use crate::web::mw_auth::{mw_ctx_require, mw_ctx_resolve};
+use crate::web::mw_req_stamp::mw_req_stamp;
 use crate::web::mw_res_map::mw_reponse_map;
-use crate::web::{routes_login, routes_static};
+use crate::web::{routes_health, routes_login, routes_metrics, routes_static};
 use axum::response::Html;
 use axum::routing::get;
 use axum::{middleware, Router};
 use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::signal;
+use tokio::sync::oneshot;
 use tower_cookies::CookieManagerLayer;
-use tracing::debug;
-use tracing_subscriber::EnvFilter;
+use tracing::{debug, error, info, warn};
 
 // endregion: --- Modules
 
 #[tokio::main]
 async fn main() -> Result<()> {
-	// Tracing
-	tracing_subscriber::fmt()
-		.without_time() // For early local development
-		.with_target(false)
-		.with_env_filter(EnvFilter::from_default_env())
-		.init();
+	// Tracing (and the optional otlp export)
+	trace::init_tracing(config())?;
 
 	// -- FOR DEV ONLY
-	_dev_utils::init_dev().await;
+	if config().RUN_MODE == RunMode::Dev {
+		_dev_utils::init_dev().await;
+	}
+
+	// -- Metrics (before any request is recorded)
+	let metrics_handle = metric::install_recorder()?;
+
+	// -- Request log sinks
+	log::init_sinks(config());
 
 	// Initialize ModelManager.
 	let mm = ModelManager::new().await?;
 
+	// -- Migrate the db schema (`migrate` only migrates, then exits)
+	let versions = mm.migrate().await?;
+	info!("{:<12} - applied: {versions:?}", "MIGRATIONS");
+	if std::env::args().nth(1).as_deref() == Some("migrate") {
+		return Ok(());
+	}
+
 	// -- Define Routes
 	let routes_rpc =
 		rpc::routes(mm.clone());
@@ -56,16 +73,73 @@ async fn main() -> Result<()> {
 		.layer(middleware::map_response(mw_reponse_map))
 		.layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
 		.layer(CookieManagerLayer::new())
-		.fallback_service(routes_static::serve_dir());
+		// after the layers, so the probes and metrics bypass them
+		.merge(routes_health::routes(mm.clone()))
+		.merge(routes_metrics::routes(mm.clone(), metrics_handle))
+		.fallback_service(routes_static::serve_dir())
+		// the outermost, for every response to have the request id
+		.layer(middleware::from_fn(mw_req_stamp));
 
 	// region:    --- Start Server
-	let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
+	let addr = SocketAddr::from((config().HOST, config().PORT));
 	debug!("{:<12} - {addr}\n", "LISTENING");
-	axum::Server::bind(&addr)
-		.serve(routes_all.into_make_service())
-		.await
-		.unwrap();
+	let (signal_tx, signal_rx) = oneshot::channel::<()>();
+	let mut server = tokio::spawn(
+		axum::Server::bind(&addr)
+			.serve(routes_all.into_make_service_with_connect_info::<SocketAddr>())
+			.with_graceful_shutdown(async {
+				shutdown_signal().await;
+				let _ = signal_tx.send(());
+			}),
+	);
+
+	// -- Graceful shutdown
+	// (also when the server stopped on its own, its error is then returned)
+	let _ = signal_rx.await;
+	let drain = Duration::from_secs(config().SHUTDOWN_TIMEOUT_SEC);
+	let served = match tokio::time::timeout(drain, &mut server).await {
+		Ok(joined) => joined
+			.map_err(Error::ServerTask)
+			.and_then(|res| res.map_err(Error::Server)),
+		Err(_) => {
+			warn!("{:<12} - in-flight requests aborted after {drain:?}", "SHUTDOWN");
+			server.abort();
+			Ok(())
+		}
+	};
+	if let Err(err) = &served {
+		error!("{:<12} - server failed: {err:?}", "SHUTDOWN");
+	}
+	log::flush().await;
+	trace::shutdown_tracing().await;
+	mm.close().await;
+	info!("{:<12} - done", "SHUTDOWN");
 	// endregion: --- Start Server
 
-	Ok(())
+	served
+}
+
+/// Resolves on ctrl-c, or SIGTERM on unix (e.g., `docker stop`).
+async fn shutdown_signal() {
+	let ctrl_c = async {
+		let _ = signal::ctrl_c().await;
+	};
+
+	#[cfg(unix)]
+	let terminate = async {
+		match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+			Ok(mut sigterm) => {
+				sigterm.recv().await;
+			}
+			Err(_) => std::future::pending().await,
+		}
+	};
+	#[cfg(not(unix))]
+	let terminate = std::future::pending::<()>();
+
+	tokio::select! {
+		_ = ctrl_c => {},
+		_ = terminate => {},
+	}
+	info!("{:<12} - signal received, draining", "SHUTDOWN");
 }
@@ -0,0 +1,107 @@
+//! The Prometheus metrics (see `web::routes_metrics` for the endpoint).
+//!
+//! Note: Without `install_recorder` (e.g., in the tests), the recording
+//!       functions are no-ops.
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::model::PoolStatus;
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::Duration;
+
+/// The buckets of the `*_duration_seconds` histograms.
+const DURATION_BUCKETS: &[f64] = &[
+	0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs the global recorder, its handle renders the metrics.
+pub fn install_recorder() -> Result<PrometheusHandle> {
+	PrometheusBuilder::new()
+		.set_buckets_for_metric(
+			Matcher::Suffix("duration_seconds".to_string()),
+			DURATION_BUCKETS,
+		)
+		.map_err(|err| Error::Build(err.to_string()))?
+		.install_recorder()
+		.map_err(|err| Error::Build(err.to_string()))
+}
+
+/// One http request, with its rpc method (if a rpc call), and its
+/// `client_error` variant (if failed).
+pub struct RequestMetric<'a> {
+	/// The route (e.g., `/api/rpc`), not the uri, to bound the cardinality.
+	pub http_path: &'a str,
+	pub http_method: &'a str,
+	pub status: u16,
+	pub rpc_method: Option<&'a str>,
+	pub client_error: Option<&'a str>,
+	pub duration: Duration,
+}
+
+pub fn record_request(req: RequestMetric) {
+	let secs = req.duration.as_secs_f64();
+	let path = req.http_path.to_string();
+
+	counter!(
+		"http_requests_total",
+		1,
+		"method" => req.http_method.to_string(),
+		"path" => path.clone(),
+		"status" => req.status.to_string()
+	);
+	histogram!(
+		"http_request_duration_seconds",
+		secs,
+		"method" => req.http_method.to_string(),
+		"path" => path.clone()
+	);
+	if let Some(client_error) = req.client_error {
+		counter!(
+			"http_request_errors_total",
+			1,
+			"path" => path,
+			"client_error" => client_error.to_string()
+		);
+	}
+
+	if let Some(rpc_method) = req.rpc_method {
+		counter!("rpc_requests_total", 1, "rpc_method" => rpc_method.to_string());
+		histogram!(
+			"rpc_request_duration_seconds",
+			secs,
+			"rpc_method" => rpc_method.to_string()
+		);
+		if let Some(client_error) = req.client_error {
+			counter!(
+				"rpc_request_errors_total",
+				1,
+				"rpc_method" => rpc_method.to_string(),
+				"client_error" => client_error.to_string()
+			);
+		}
+	}
+}
+
+pub fn record_login(success: bool) {
+	let result = if success { "success" } else { "failure" };
+	counter!("login_attempts_total", 1, "result" => result);
+}
+
+/// Note: Called on scrape, the pool having no change events.
+pub fn record_db_pool(pool: &PoolStatus) {
+	gauge!("db_pool_connections", pool.size as f64, "state" => "open");
+	gauge!("db_pool_connections", pool.idle as f64, "state" => "idle");
+	gauge!("db_pool_max_connections", pool.max as f64);
+}
+
+/// A request log line dropped, the sinks being behind (see `log::sink`).
+pub fn record_log_dropped() {
+	counter!("request_log_dropped_total", 1);
+}
+
+pub fn record_log_sink_error(sink: &'static str) {
+	counter!("request_log_sink_errors_total", 1, "sink" => sink);
+}
@@ -1,4 +1,8 @@
-use super::{Error, ModelManager, Result};
+use super::entity_history::{
+	changes_of, field_names, EntityHistoryBmc, HistoryOp,
+};
+use super::page::{Cursor, HasId, Page, PageOptions};
+use super::{BulkItemError, Error, ModelManager, Result};
 use crate::ctx::Ctx;
 use modql::{
 	field::HasFields,
@@ -6,27 +10,61 @@ use modql::{
 	SIden,
 };
 use sea_query::{
-	Condition, Expr, Iden, IntoIden, PostgresQueryBuilder, Query, ReturningClause,
-	TableRef,
+	Asterisk, Condition, Expr, Iden, IntoIden, Order, PostgresQueryBuilder, Query,
+	ReturningClause, TableRef,
 };
 use sea_query_binder::SqlxBinder;
+use serde::Serialize;
 use sqlx::{postgres::PgRow, FromRow};
+use time::Duration;
+
+use crate::utils::now_utc;
 
 #[derive(Iden)]
 enum CommonIden {
 	Id,
+	DeletedAt,
+	Version,
 }
 
 pub trait DbBmc {
 	const TABLE: &'static str;
 
+	/// When true, the table has a `deleted_at` column, `delete` only sets it,
+	/// and `get`/`list`/`update` ignore the deleted rows.
+	const SOFT_DELETE: bool = false;
+
 	fn table_ref() -> TableRef {
 		TableRef::Table(SIden(Self::TABLE).into_iden())
 	}
 }
 
+/// Adds the soft delete scope (deleted or not deleted rows) to `condition`,
+/// for the `DbBmc::SOFT_DELETE` entities.
+pub(super) fn scoped_condition<MC>(
+	condition: Option<Condition>,
+	deleted: bool,
+) -> Option<Condition>
+where
+	MC: DbBmc,
+{
+	if !MC::SOFT_DELETE {
+		return condition;
+	}
+
+	let deleted_at = Expr::col(CommonIden::DeletedAt);
+	let scope = if deleted {
+		deleted_at.is_not_null()
+	} else {
+		deleted_at.is_null()
+	};
+
+	Some(Condition::all().add_option(condition).add(scope))
+}
+
 const LIST_LIMIT_MAX: i64 = 100;
 const LIST_LIMIT_DEFAULT: i64 = 20;
+const BULK_MAX: i64 = 1000;
 
 pub fn finalize_list_options(
 	list_options: Option<ListOptions>,
@@ -56,7 +94,7 @@ where
 	MC: DbBmc,
 	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields,
 {
-	let db = mm.db();
+	let dbx = mm.dbx();
 
 	// build query
 	let mut query = Query::select();
@@ -64,16 +102,20 @@ where
 		.from(MC::table_ref())
 		.columns(E::field_column_refs())
 		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(scope) = scoped_condition::<MC>(None, false) {
+		query.cond_where(scope);
+	}
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 
 	// exec query
-	let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_optional(db)
-		.await?
-		.ok_or(Error::EntityNotFound {
-			entity: MC::TABLE,
-			id,
-		})?;
+	let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+	let entity =
+		dbx.fetch_optional(sqlx_query)
+			.await?
+			.ok_or(Error::EntityNotFound {
+				entity: MC::TABLE,
+				id,
+			})?;
 
 	Ok(entity)
 }
@@ -89,14 +131,35 @@ where
 	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields,
 	F: Into<FilterGroups>,
 {
-	let db = mm.db();
+	let condition = match filters {
+		Some(filters) => {
+			let filters: FilterGroups = filters.into();
+			Some(Condition::try_from(filters)?)
+		}
+		None => None,
+	};
+
+	list_by_condition::<MC, E>(ctx, mm, condition, list_options).await
+}
+
+/// Same as `list` but with an already built condition, for the Bmcs that
+/// need conditions modql filters cannot express (e.g., ctx or join based).
+pub async fn list_by_condition<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	condition: Option<Condition>,
+	list_options: Option<ListOptions>,
+) -> Result<Vec<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields,
+{
+	let dbx = mm.dbx();
 	// build query
 	let mut query = Query::select();
 	query.from(MC::table_ref()).columns(E::field_column_refs());
 
-	if let Some(filters) = filters {
-		let filters: FilterGroups = filters.into();
-		let condition: Condition = filters.try_into()?;
+	if let Some(condition) = scoped_condition::<MC>(condition, false) {
 		query.cond_where(condition);
 	}
 
@@ -105,13 +168,171 @@ where
 
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 
-	let entities = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_all(db)
-		.await?;
+	let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+	let entities = dbx.fetch_all(sqlx_query).await?;
 
 	Ok(entities)
 }
 
+/// Same as `list` but returns a keyset `Page` (see `list_page_by_condition`).
+pub async fn list_page<MC, E, F>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	filters: Option<F>,
+	list_options: Option<ListOptions>,
+	page_options: PageOptions,
+) -> Result<Page<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId,
+	F: Into<FilterGroups>,
+{
+	let condition = match filters {
+		Some(filters) => {
+			let filters: FilterGroups = filters.into();
+			Some(Condition::try_from(filters)?)
+		}
+		None => None,
+	};
+
+	list_page_by_condition::<MC, E>(ctx, mm, condition, list_options, page_options)
+		.await
+}
+
+/// Same as `list_by_condition` but returns a keyset `Page`.
+///
+/// The items are walked by ascending id (the `list_options.offset` is ignored
+/// when a cursor is given). When the client asks for a custom `order_bys`,
+/// the page is offset based and has no cursors, and a cursor is an
+/// `Error::InvalidCursor`.
+pub async fn list_page_by_condition<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	condition: Option<Condition>,
+	list_options: Option<ListOptions>,
+	page_options: PageOptions,
+) -> Result<Page<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId,
+{
+	let condition = scoped_condition::<MC>(condition, false);
+	page_by_condition::<MC, E>(mm, condition, list_options, page_options).await
+}
+
+/// Same as `list_page_by_condition` but on the soft deleted rows only.
+pub async fn list_deleted_page_by_condition<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	condition: Option<Condition>,
+	list_options: Option<ListOptions>,
+	page_options: PageOptions,
+) -> Result<Page<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId,
+{
+	ensure_soft_delete::<MC>()?;
+	let condition = scoped_condition::<MC>(condition, true);
+	page_by_condition::<MC, E>(mm, condition, list_options, page_options).await
+}
+
+/// `condition` must already be scoped (see `scoped_condition`).
+async fn page_by_condition<MC, E>(
+	mm: &ModelManager,
+	condition: Option<Condition>,
+	list_options: Option<ListOptions>,
+	page_options: PageOptions,
+) -> Result<Page<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId,
+{
+	let dbx = mm.dbx();
+	let cursor = page_options
+		.cursor
+		.as_deref()
+		.map(Cursor::decode)
+		.transpose()?;
+	let keyset = list_options.as_ref().is_none_or(|o| o.order_bys.is_none());
+	if !keyset && cursor.is_some() {
+		return Err(Error::InvalidCursor {
+			cursor: page_options.cursor.unwrap_or_default(),
+		});
+	}
+	let mut list_options = finalize_list_options(list_options)?;
+	// finalize_list_options always sets the limit.
+	let limit = list_options.limit.unwrap_or(LIST_LIMIT_DEFAULT);
+
+	// -- Total (without the cursor condition)
+	let total = if page_options.with_total {
+		let mut query = Query::select();
+		query
+			.from(MC::table_ref())
+			.expr(Expr::col(Asterisk).count());
+		if let Some(condition) = condition.clone() {
+			query.cond_where(condition);
+		}
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+		let (total,) = dbx.fetch_one(sqlx_query).await?;
+		Some(total)
+	} else {
+		None
+	};
+
+	// build query
+	let mut query = Query::select();
+	query.from(MC::table_ref()).columns(E::field_column_refs());
+
+	if let Some(condition) = condition {
+		query.cond_where(condition);
+	}
+
+	if keyset {
+		list_options.limit = Some(limit + 1);
+		if cursor.is_some() {
+			list_options.offset = None;
+		}
+		let id_col = Expr::col(CommonIden::Id);
+		match cursor {
+			Some(Cursor::Next(id)) => {
+				query.and_where(id_col.gt(id));
+				query.order_by(CommonIden::Id, Order::Asc);
+			}
+			Some(Cursor::Prev(id)) => {
+				query.and_where(id_col.lt(id));
+				query.order_by(CommonIden::Id, Order::Desc);
+			}
+			None => {
+				query.order_by(CommonIden::Id, Order::Asc);
+			}
+		}
+	}
+	list_options.apply_to_sea_query(&mut query);
+
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+	// exec query
+	let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+	let entities = dbx.fetch_all(sqlx_query).await?;
+
+	let page = if keyset {
+		Page::from_fetched(entities, limit as usize, cursor.as_ref(), total)
+	} else {
+		Page {
+			items: entities,
+			next_cursor: None,
+			prev_cursor: None,
+			total,
+		}
+	};
+
+	Ok(page)
+}
+
+/// Note: The change and its `entity_history` row are in one transaction
+///       (the same for `update`, `delete` and `restore`).
 pub async fn create<MC, E, EC>(
 	ctx: &Ctx,
 	mm: &ModelManager,
@@ -119,87 +340,379 @@ pub async fn create<MC, E, EC>(
 ) -> Result<E>
 where
 	MC: DbBmc,
-	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId + Serialize,
 	EC: HasFields,
 {
-	let db = mm.db();
-	// extract fields
-	let fields = payload.not_none_fields();
-	let (columns, sea_values) = fields.for_sea_insert();
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
 
-	// build query
-	let mut query = Query::insert();
-	query
-		.into_table(MC::table_ref())
-		.columns(columns)
-		.values(sea_values)?
-		.returning(ReturningClause::All);
-	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let res = async {
+		let dbx = mm.dbx();
+		// extract fields
+		let fields = payload.not_none_fields();
+		let names = field_names(&fields);
+		let (columns, sea_values) = fields.for_sea_insert();
 
-	// exec query
-	let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_one(db)
+		// build query
+		let mut query = Query::insert();
+		query
+			.into_table(MC::table_ref())
+			.columns(columns)
+			.values(sea_values)?
+			.returning(ReturningClause::All);
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+		// exec query
+		let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+		let entity: E = dbx.fetch_one(sqlx_query).await?;
+
+		let changes = changes_of(&entity, &names)?;
+		EntityHistoryBmc::record(
+			ctx,
+			&mm,
+			MC::TABLE,
+			entity.id(),
+			HistoryOp::Create,
+			changes,
+		)
 		.await?;
 
-	Ok(entity)
+		Ok(entity)
+	}
+	.await;
+
+	mm.end_txn(res).await
 }
 
+/// Soft delete (see `DbBmc::SOFT_DELETE`) or hard delete the row `id`.
 pub async fn delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
 where
 	MC: DbBmc,
 {
-	let db = mm.db();
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+
+	let res = async {
+		let dbx = mm.dbx();
+
+		// build query
+		let (sql, values) = if MC::SOFT_DELETE {
+			let mut query = Query::update();
+			query
+				.table(MC::table_ref())
+				.value(CommonIden::DeletedAt, now_utc())
+				.and_where(Expr::col(CommonIden::Id).eq(id))
+				.and_where(Expr::col(CommonIden::DeletedAt).is_null());
+			query.build_sqlx(PostgresQueryBuilder)
+		} else {
+			let mut query = Query::delete();
+			query
+				.from_table(MC::table_ref())
+				.and_where(Expr::col(CommonIden::Id).eq(id));
+			query.build_sqlx(PostgresQueryBuilder)
+		};
+
+		// exec query
+		let sqlx_query = sqlx::query_with(&sql, values);
+		let count = dbx.execute(sqlx_query).await?;
+		if count != 1 {
+			return Err(Error::EntityNotFound {
+				entity: MC::TABLE,
+				id,
+			});
+		}
+
+		EntityHistoryBmc::record(ctx, &mm, MC::TABLE, id, HistoryOp::Delete, None)
+			.await
+	}
+	.await;
+
+	mm.end_txn(res).await
+}
+
+// region:    --- Soft Delete
+
+/// Restores the soft deleted row `id`.
+pub async fn restore<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
+	ensure_soft_delete::<MC>()?;
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+
+	let res = async {
+		let dbx = mm.dbx();
+
+		// build query
+		let mut query = Query::update();
+		query
+			.table(MC::table_ref())
+			.value(CommonIden::DeletedAt, Option::<time::OffsetDateTime>::None)
+			.and_where(Expr::col(CommonIden::Id).eq(id))
+			.and_where(Expr::col(CommonIden::DeletedAt).is_not_null());
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_with(&sql, values);
+		let count = dbx.execute(sqlx_query).await?;
+		if count != 1 {
+			return Err(Error::EntityNotFound {
+				entity: MC::TABLE,
+				id,
+			});
+		}
+
+		EntityHistoryBmc::record(ctx, &mm, MC::TABLE, id, HistoryOp::Restore, None)
+			.await
+	}
+	.await;
+
+	mm.end_txn(res).await
+}
+
+/// Hard deletes the rows soft deleted for longer than `older_than`.
+/// Returns the number of purged rows.
+pub async fn purge_deleted<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	older_than: Duration,
+) -> Result<u64>
+where
+	MC: DbBmc,
+{
+	ensure_soft_delete::<MC>()?;
+	let dbx = mm.dbx();
 
 	// build query
 	let mut query = Query::delete();
 	query
 		.from_table(MC::table_ref())
-		.and_where(Expr::col(CommonIden::Id).eq(id));
+		.and_where(Expr::col(CommonIden::DeletedAt).lte(now_utc() - older_than));
 
 	// exec query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let count = sqlx::query_with(&sql, values)
-		.execute(db)
-		.await?
-		.rows_affected();
-	if count != 1 {
-		return Err(Error::EntityNotFound {
-			entity: MC::TABLE,
-			id,
-		});
+	let sqlx_query = sqlx::query_with(&sql, values);
+	let count = dbx.execute(sqlx_query).await?;
+
+	Ok(count)
+}
+
+fn ensure_soft_delete<MC>() -> Result<()>
+where
+	MC: DbBmc,
+{
+	if !MC::SOFT_DELETE {
+		return Err(Error::SoftDeleteNotSupported { entity: MC::TABLE });
 	}
 	Ok(())
 }
 
+// endregion: --- Soft Delete
+
+/// Updates the entity and increments its `version`.
+///
+/// When `version` is given (e.g., the one the client read), the entity is only
+/// updated at this version, otherwise it is an `Error::VersionConflict` with
+/// the current entity. `None` is for the server side updates only.
 pub async fn update<MC, E, EU>(
 	ctx: &Ctx,
 	mm: &ModelManager,
 	id: i64,
+	version: Option<i64>,
 	payload: EU,
 ) -> Result<E>
 where
 	MC: DbBmc,
-	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + Serialize,
 	EU: HasFields,
 {
-	let db = mm.db();
-	// extract fields
-	let fields = payload.not_none_fields();
-	let fields = fields.for_sea_update();
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
 
-	// build query
-	let mut query = Query::update();
-	query
-		.table(MC::table_ref())
-		.values(fields)
-		.and_where(Expr::col(CommonIden::Id).eq(id))
-		.returning(ReturningClause::All);
-	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let res = async {
+		let dbx = mm.dbx();
+		// extract fields
+		let fields = payload.not_none_fields();
+		let names = field_names(&fields);
+		let fields = fields.for_sea_update();
 
-	// exec query
-	let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-		.fetch_one(db)
-		.await?;
+		// build query
+		let mut query = Query::update();
+		query
+			.table(MC::table_ref())
+			.values(fields)
+			.value(CommonIden::Version, Expr::col(CommonIden::Version).add(1))
+			.and_where(Expr::col(CommonIden::Id).eq(id))
+			.returning(ReturningClause::All);
+		if let Some(version) = version {
+			query.and_where(Expr::col(CommonIden::Version).eq(version));
+		}
+		if let Some(scope) = scoped_condition::<MC>(None, false) {
+			query.cond_where(scope);
+		}
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
 
-	Ok(entity)
+		// exec query
+		let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+		if let Some(entity) = dbx.fetch_optional(sqlx_query).await? {
+			let changes = changes_of(&entity, &names)?;
+			EntityHistoryBmc::record(
+				ctx,
+				&mm,
+				MC::TABLE,
+				id,
+				HistoryOp::Update,
+				changes,
+			)
+			.await?;
+			return Ok(entity);
+		}
+
+		// -- Not found, or at another version.
+		let Some(version) = version else {
+			return Err(Error::EntityNotFound {
+				entity: MC::TABLE,
+				id,
+			});
+		};
+		let current: E = get::<MC, _>(ctx, &mm, id).await?;
+
+		Err(Error::VersionConflict {
+			entity: MC::TABLE,
+			id,
+			expected: version,
+			current: serde_json::to_value(current)?,
+		})
+	}
+	.await;
+
+	mm.end_txn(res).await
+}
+
+// region:    --- Bulk
+
+// All of the items of a bulk operation run in one transaction (nested in the
+// mm one if any), each in its own nested level (savepoint) so that a failing
+// item does not hide the errors of the next ones. If any item fails, the bulk
+// transaction is rolled back.
+
+pub async fn create_many<MC, E, EC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	payloads: Vec<EC>,
+) -> Result<Vec<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + HasId + Serialize,
+	EC: HasFields,
+{
+	ensure_bulk_max(payloads.len())?;
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+
+	let res = async {
+		let mut entities = Vec::with_capacity(payloads.len());
+		let mut errors = Vec::new();
+		for (index, payload) in payloads.into_iter().enumerate() {
+			mm.begin_txn().await?;
+			let res = create::<MC, E, EC>(ctx, &mm, payload).await;
+			match mm.end_txn(res).await {
+				Ok(entity) => entities.push(entity),
+				Err(err) => errors.push(BulkItemError::new(index, err)),
+			}
+		}
+		bulk_result::<MC, _>(entities, errors)
+	}
+	.await;
+
+	mm.end_txn(res).await
+}
+
+/// `items` are `(id, version, payload)` (see `update`).
+pub async fn update_many<MC, E, EU>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	items: Vec<(i64, Option<i64>, EU)>,
+) -> Result<Vec<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send + HasFields + Serialize,
+	EU: HasFields,
+{
+	ensure_bulk_max(items.len())?;
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+
+	let res = async {
+		let mut entities = Vec::with_capacity(items.len());
+		let mut errors = Vec::new();
+		for (index, (id, version, payload)) in items.into_iter().enumerate() {
+			mm.begin_txn().await?;
+			let res = update::<MC, E, EU>(ctx, &mm, id, version, payload).await;
+			match mm.end_txn(res).await {
+				Ok(entity) => entities.push(entity),
+				Err(err) => errors.push(BulkItemError::new(index, err)),
+			}
+		}
+		bulk_result::<MC, _>(entities, errors)
+	}
+	.await;
+
+	mm.end_txn(res).await
+}
+
+pub async fn delete_many<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	ids: Vec<i64>,
+) -> Result<()>
+where
+	MC: DbBmc,
+{
+	ensure_bulk_max(ids.len())?;
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+
+	let res = async {
+		let mut errors = Vec::new();
+		for (index, id) in ids.into_iter().enumerate() {
+			mm.begin_txn().await?;
+			let res = delete::<MC>(ctx, &mm, id).await;
+			if let Err(err) = mm.end_txn(res).await {
+				errors.push(BulkItemError::new(index, err));
+			}
+		}
+		bulk_result::<MC, _>((), errors)
+	}
+	.await;
+
+	mm.end_txn(res).await
 }
+
+fn ensure_bulk_max(len: usize) -> Result<()> {
+	let len = len as i64;
+	if len > BULK_MAX {
+		return Err(Error::BulkLimitExceeded {
+			max: BULK_MAX,
+			actual: len,
+		});
+	}
+	Ok(())
+}
+
+fn bulk_result<MC, T>(val: T, errors: Vec<BulkItemError>) -> Result<T>
+where
+	MC: DbBmc,
+{
+	if errors.is_empty() {
+		Ok(val)
+	} else {
+		Err(Error::BulkFailed {
+			entity: MC::TABLE,
+			errors,
+		})
+	}
+}
+
+// endregion: --- Bulk
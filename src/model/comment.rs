@@ -0,0 +1,346 @@
+use modql::{
+	field::Fields,
+	filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use time::OffsetDateTime;
+
+use super::base::{self, DbBmc};
+use super::modql_utils::time_to_sea_value;
+use super::page::{HasId, Page, PageOptions};
+use super::task::TaskBmc;
+use super::{Error, ModelManager, Result};
+use crate::ctx::Ctx;
+use crate::utils::{now_utc, serialize_time};
+
+#[derive(Clone, Debug, FromRow, Fields, Serialize)]
+pub struct Comment {
+	pub id: i64,
+	pub task_id: i64,
+	pub author_id: i64,
+	pub content: String,
+	#[serde(serialize_with = "serialize_time")]
+	pub ctime: OffsetDateTime,
+	#[serde(serialize_with = "serialize_time")]
+	pub mtime: OffsetDateTime,
+	pub version: i64,
+}
+
+impl HasId for Comment {
+	fn id(&self) -> i64 {
+		self.id
+	}
+}
+
+#[derive(Deserialize)]
+pub struct CommentForCreate {
+	pub task_id: i64,
+	pub content: String,
+}
+
+#[derive(Fields)]
+struct CommentForInsert {
+	task_id: i64,
+	author_id: i64,
+	content: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct CommentForUpdate {
+	pub content: Option<String>,
+}
+
+#[derive(Fields)]
+struct CommentForEdit {
+	content: Option<String>,
+	mtime: OffsetDateTime,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct CommentFilter {
+	id: Option<OpValsInt64>,
+	task_id: Option<OpValsInt64>,
+	author_id: Option<OpValsInt64>,
+	content: Option<OpValsString>,
+	#[modql(to_sea_value_fn = "time_to_sea_value")]
+	ctime: Option<OpValsValue>,
+}
+
+pub struct CommentBmc;
+
+impl DbBmc for CommentBmc {
+	const TABLE: &'static str = "comment";
+}
+
+impl CommentBmc {
+	/// Create a comment on `task_id` authored by the ctx user.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		payload: CommentForCreate,
+	) -> Result<Comment> {
+		let CommentForCreate { task_id, content } = payload;
+
+		// Make sure the task exists (for a clean EntityNotFound).
+		TaskBmc::get(ctx, mm, task_id).await?;
+
+		base::create::<Self, _, _>(
+			ctx,
+			mm,
+			CommentForInsert {
+				task_id,
+				author_id: ctx.user_id(),
+				content,
+			},
+		)
+		.await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Comment> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	pub async fn list(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<CommentFilter>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Comment>> {
+		base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+	}
+
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<CommentFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<Comment>> {
+		base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options)
+			.await
+	}
+
+	/// Only the author (or an admin) can edit a comment.
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+		payload: CommentForUpdate,
+	) -> Result<Comment> {
+		Self::ensure_author_or_admin(ctx, mm, id).await?;
+
+		let CommentForUpdate { content } = payload;
+		base::update::<Self, _, _>(
+			ctx,
+			mm,
+			id,
+			version,
+			CommentForEdit {
+				content,
+				mtime: now_utc(),
+			},
+		)
+		.await
+	}
+
+	/// Only the author (or an admin) can delete a comment.
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		Self::ensure_author_or_admin(ctx, mm, id).await?;
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	async fn ensure_author_or_admin(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<Comment> {
+		let comment = Self::get(ctx, mm, id).await?;
+
+		if comment.author_id != ctx.user_id() && !ctx.is_admin() {
+			return Err(Error::AccessDenied {
+				entity: Self::TABLE,
+				user_id: ctx.user_id(),
+			});
+		}
+
+		Ok(comment)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::TaskBmc;
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_comment_create_list_update() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		let tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_comment_task"]).await?;
+		let task_id = tasks[0].id;
+
+		// Create
+		for content in ["comment 1", "comment 2", "comment 3"] {
+			CommentBmc::create(
+				&ctx,
+				&mm,
+				CommentForCreate {
+					task_id,
+					content: content.to_string(),
+				},
+			)
+			.await?;
+		}
+
+		// List (paginated)
+		let filter: CommentFilter =
+			serde_json::from_value(json!({ "task_id": task_id }))?;
+		let list_options: ListOptions = serde_json::from_value(json!({
+			"limit": 2,
+			"offset": 1,
+			"order_bys": "id",
+		}))?;
+		let comments =
+			CommentBmc::list(&ctx, &mm, Some(vec![filter]), Some(list_options))
+				.await?;
+		assert_eq!(comments.len(), 2, "number of comments in page");
+		assert_eq!(comments[0].content, "comment 2");
+		assert_eq!(comments[0].author_id, ctx.user_id());
+
+		// Update
+		let comment = CommentBmc::update(
+			&ctx,
+			&mm,
+			comments[0].id,
+			Some(comments[0].version),
+			CommentForUpdate {
+				content: Some("comment 2 edited".to_string()),
+			},
+		)
+		.await?;
+		assert_eq!(comment.content, "comment 2 edited");
+		assert!(comment.mtime >= comment.ctime);
+
+		// Cleanup (cascade deletes the comments)
+		TaskBmc::delete(&ctx, &mm, task_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_comment_not_author_denied() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		// demo2 (not the author, not an admin)
+		let other_ctx = Ctx::new(1002)?;
+		let tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_comment_denied_task"]).await?;
+		let task_id = tasks[0].id;
+		let comment = CommentBmc::create(
+			&ctx,
+			&mm,
+			CommentForCreate {
+				task_id,
+				content: "comment".to_string(),
+			},
+		)
+		.await?;
+
+		// Execute
+		let update_res = CommentBmc::update(
+			&other_ctx,
+			&mm,
+			comment.id,
+			Some(comment.version),
+			CommentForUpdate {
+				content: Some("not mine".to_string()),
+			},
+		)
+		.await;
+		let delete_res = CommentBmc::delete(&other_ctx, &mm, comment.id).await;
+
+		// Check
+		assert!(
+			matches!(
+				update_res,
+				Err(Error::AccessDenied {
+					entity: "comment",
+					..
+				})
+			),
+			"update should be denied {update_res:?}"
+		);
+		assert!(
+			matches!(
+				delete_res,
+				Err(Error::AccessDenied {
+					entity: "comment",
+					..
+				})
+			),
+			"delete should be denied {delete_res:?}"
+		);
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, task_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_comment_admin_allowed() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		// admin (not the author)
+		let admin_ctx = Ctx::new_admin(1001)?;
+		let tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_comment_admin_task"]).await?;
+		let task_id = tasks[0].id;
+		let comment = CommentBmc::create(
+			&ctx,
+			&mm,
+			CommentForCreate {
+				task_id,
+				content: "comment".to_string(),
+			},
+		)
+		.await?;
+
+		// Execute
+		let comment = CommentBmc::update(
+			&admin_ctx,
+			&mm,
+			comment.id,
+			Some(comment.version),
+			CommentForUpdate {
+				content: Some("moderated".to_string()),
+			},
+		)
+		.await?;
+		CommentBmc::delete(&admin_ctx, &mm, comment.id).await?;
+
+		// Check
+		assert_eq!(comment.content, "moderated");
+		assert_eq!(comment.author_id, ctx.user_id(), "author unchanged");
+		let res = CommentBmc::get(&ctx, &mm, comment.id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { entity: "comment", .. })),
+			"comment should be deleted {res:?}"
+		);
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, task_id).await?;
+
+		Ok(())
+	}
+}
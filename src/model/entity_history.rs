@@ -0,0 +1,219 @@
+//! Append-only history of the changes made through `model::base`
+//! (create/update/delete/restore), and of the links between entities
+//! (e.g., task dependencies), one row per change.
+
+use modql::field::{Fields, HasFields};
+use modql::filter::ListOptions;
+use sea_query::{Condition, Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::prelude::FromRow;
+use time::OffsetDateTime;
+
+use super::base::{self, DbBmc};
+use super::page::{HasId, Page, PageOptions};
+use super::{ModelManager, Result};
+use crate::ctx::Ctx;
+use crate::utils::serialize_time;
+
+#[derive(Clone, Debug, FromRow, Fields, Serialize)]
+pub struct EntityHistory {
+	pub id: i64,
+	pub entity: String,
+	pub entity_id: i64,
+	/// The ctx user who made the change (0 for the root ctx).
+	pub user_id: i64,
+	pub op: String,
+	/// The changed fields with their new value (none for a delete).
+	pub changes: Option<Value>,
+	#[serde(serialize_with = "serialize_time")]
+	pub ctime: OffsetDateTime,
+}
+
+impl HasId for EntityHistory {
+	fn id(&self) -> i64 {
+		self.id
+	}
+}
+
+#[derive(Clone, Copy, Debug, strum_macros::AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum HistoryOp {
+	Create,
+	Update,
+	Delete,
+	Restore,
+	/// A link added to the entity (e.g., a dependency), `changes` has the
+	/// linked id (e.g., `{"depends_on_id": 1001}`).
+	Link,
+	Unlink,
+}
+
+#[derive(Fields)]
+struct EntityHistoryForInsert {
+	entity: String,
+	entity_id: i64,
+	user_id: i64,
+	op: String,
+	changes: Option<Value>,
+}
+
+#[derive(Iden)]
+enum EntityHistoryIden {
+	Entity,
+	EntityId,
+}
+
+pub struct EntityHistoryBmc;
+
+impl DbBmc for EntityHistoryBmc {
+	const TABLE: &'static str = "entity_history";
+}
+
+impl EntityHistoryBmc {
+	/// Note: Called by `model::base` (and the Bmc link functions) in the
+	///       transaction of the change.
+	pub(in crate::model) async fn record(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		entity: &'static str,
+		entity_id: i64,
+		op: HistoryOp,
+		changes: Option<Value>,
+	) -> Result<()> {
+		let dbx = mm.dbx();
+		// extract fields
+		let fields = EntityHistoryForInsert {
+			entity: entity.to_string(),
+			entity_id,
+			user_id: ctx.user_id(),
+			op: op.as_ref().to_string(),
+			changes,
+		}
+		.not_none_fields();
+		let (columns, sea_values) = fields.for_sea_insert();
+
+		// build query
+		let mut query = Query::insert();
+		query
+			.into_table(Self::table_ref())
+			.columns(columns)
+			.values(sea_values)?;
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+		// exec query
+		let sqlx_query = sqlx::query_with(&sql, values);
+		dbx.execute(sqlx_query).await?;
+
+		Ok(())
+	}
+
+	/// The history of the `entity` row `entity_id`, oldest first
+	/// (see `base::list_page_by_condition`).
+	pub async fn list_page_for_entity(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		entity: &str,
+		entity_id: i64,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<EntityHistory>> {
+		let condition = Condition::all()
+			.add(Expr::col(EntityHistoryIden::Entity).eq(entity))
+			.add(Expr::col(EntityHistoryIden::EntityId).eq(entity_id));
+		base::list_page_by_condition::<Self, _>(
+			ctx,
+			mm,
+			Some(condition),
+			list_options,
+			page_options,
+		)
+		.await
+	}
+}
+
+/// The `fields` (names) of the `entity` with their new value, as a json object.
+pub(in crate::model) fn changes_of<E>(
+	entity: &E,
+	fields: &[String],
+) -> Result<Option<Value>>
+where
+	E: Serialize,
+{
+	let mut entity = match serde_json::to_value(entity)? {
+		Value::Object(entity) => entity,
+		_ => return Ok(None),
+	};
+	let changes: Map<String, Value> = fields
+		.iter()
+		.filter_map(|name| entity.remove_entry(name))
+		.collect();
+
+	Ok(Some(Value::Object(changes)))
+}
+
+/// The column names of the `fields` of a payload (see `changes_of`).
+pub(in crate::model) fn field_names(fields: &Fields) -> Vec<String> {
+	fields
+		.clone()
+		.into_iter()
+		.map(|field| field.iden.to_string())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::{TaskBmc, TaskForCreate, TaskForUpdate};
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_entity_history_task_changes() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		let task = TaskBmc::create(
+			&ctx,
+			&mm,
+			TaskForCreate {
+				title: "test_entity_history_title".to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+		let task_u: TaskForUpdate =
+			serde_json::from_value(json!({"title": "test_entity_history_new"}))?;
+		TaskBmc::update(&ctx, &mm, task.id, Some(task.version), task_u).await?;
+		TaskBmc::delete(&ctx, &mm, task.id).await?;
+
+		// Check
+		let history = EntityHistoryBmc::list_page_for_entity(
+			&ctx,
+			&mm,
+			"task",
+			task.id,
+			None,
+			PageOptions::default(),
+		)
+		.await?
+		.items;
+		let ops: Vec<&str> = history.iter().map(|h| h.op.as_str()).collect();
+		assert_eq!(ops, ["create", "update", "delete"]);
+		assert!(history.iter().all(|h| h.user_id == 1000), "user of changes");
+		assert_eq!(
+			history[0].changes,
+			Some(json!({"title": "test_entity_history_title"}))
+		);
+		assert_eq!(
+			history[1].changes,
+			Some(json!({"title": "test_entity_history_new"}))
+		);
+		assert_eq!(history[2].changes, None);
+
+		Ok(())
+	}
+}
@@ -2,7 +2,7 @@ use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
 use derive_more::From;
 
-use crate::{crypt, model::store};
+use crate::{crypt, model::store, model::store::dbx};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -17,10 +17,25 @@ pub enum Error {
     entity: &'static str,
     id: i64,
   },
+  AccessDenied {
+    entity: &'static str,
+    user_id: i64,
+  },
+  /// Linking `id` to `target_id` would create a cycle
+  /// (e.g., parent chain or dependency edges).
+  CycleDetected {
+    entity: &'static str,
+    id: i64,
+    target_id: i64,
+  },
   // -- Modules
   #[from]
   Store(store::Error),
   #[from]
+  Dbx(dbx::Error),
+  #[from]
+  Migration(store::migration::Error),
+  #[from]
   Crypt(crypt::Error),
   // -- External
   #[from]
@@ -30,10 +45,62 @@ pub enum Error {
 
   #[from]
   ModqlIntoSea(#[serde_as(as = "DisplayFromStr")] modql::filter::IntoSeaError),
+  #[from]
+  SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
 
   ListLimitExceeded {
     max: i64,
     actual: i64,
+  },
+  InvalidCursor {
+    cursor: String,
+  },
+  /// The entity is not `DbBmc::SOFT_DELETE`.
+  SoftDeleteNotSupported {
+    entity: &'static str,
+  },
+  BulkLimitExceeded {
+    max: i64,
+    actual: i64,
+  },
+  /// The entity is not at the `expected` version anymore (`current` is the
+  /// current entity, as json).
+  VersionConflict {
+    entity: &'static str,
+    id: i64,
+    expected: i64,
+    current: serde_json::Value,
+  },
+  /// Nothing was committed, `errors` has the items that failed.
+  BulkFailed {
+    entity: &'static str,
+    errors: Vec<BulkItemError>,
+  },
+}
+
+/// The error of one item (by index in the request) of a bulk operation.
+///
+/// Note: `error` is the full model error (for the server log); the web layer
+///       maps it to a client-safe error.
+#[derive(Debug, Serialize)]
+pub struct BulkItemError {
+  pub index: usize,
+  pub error: Error,
+}
+
+impl BulkItemError {
+  pub fn new(index: usize, error: Error) -> Self {
+    Self { index, error }
+  }
+}
+
+impl Error {
+  /// The underlying sqlx error, if any (direct or from the dbx executor).
+  pub fn as_sqlx(&self) -> Option<&sqlx::Error> {
+    match self {
+      Error::Sqlx(err) | Error::Dbx(dbx::Error::Sqlx(err)) => Some(err),
+      _ => None,
+    }
   }
 }
 
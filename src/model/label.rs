@@ -0,0 +1,331 @@
+use modql::{
+	field::Fields,
+	filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString},
+};
+use sea_query::{
+	Expr, Iden, OnConflict, PostgresQueryBuilder, Query, SelectStatement,
+};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use super::base::{self, DbBmc};
+use super::page::{HasId, Page, PageOptions};
+use super::task::TaskBmc;
+use super::{ModelManager, Result};
+use crate::ctx::Ctx;
+
+#[derive(Clone, Debug, FromRow, Fields, Serialize)]
+pub struct Label {
+	pub id: i64,
+	pub name: String,
+	pub color: Option<String>,
+	pub version: i64,
+}
+
+impl HasId for Label {
+	fn id(&self) -> i64 {
+		self.id
+	}
+}
+
+#[derive(Deserialize, Fields, Default)]
+pub struct LabelForCreate {
+	pub name: String,
+	pub color: Option<String>,
+}
+
+#[derive(Deserialize, Fields, Default)]
+pub struct LabelForUpdate {
+	pub name: Option<String>,
+	pub color: Option<String>,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct LabelFilter {
+	id: Option<OpValsInt64>,
+	name: Option<OpValsString>,
+	color: Option<OpValsString>,
+}
+
+#[derive(Iden)]
+enum LabelIden {
+	Id,
+}
+
+#[derive(Iden)]
+enum TaskLabelIden {
+	#[iden = "task_label"]
+	Table,
+	TaskId,
+	LabelId,
+}
+
+pub struct LabelBmc;
+
+impl DbBmc for LabelBmc {
+	const TABLE: &'static str = "label";
+}
+
+impl LabelBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		payload: LabelForCreate,
+	) -> Result<Label> {
+		base::create::<Self, _, _>(ctx, mm, payload).await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Label> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	pub async fn list(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<LabelFilter>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Label>> {
+		base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+	}
+
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<LabelFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<Label>> {
+		base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options)
+			.await
+	}
+
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+		payload: LabelForUpdate,
+	) -> Result<Label> {
+		base::update::<Self, _, _>(ctx, mm, id, version, payload).await
+	}
+
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	/// Labels attached to the task `task_id`.
+	pub async fn list_for_task(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task_id: i64,
+	) -> Result<Vec<Label>> {
+		let dbx = mm.dbx();
+
+		// build query
+		let label_ids = Query::select()
+			.column(TaskLabelIden::LabelId)
+			.from(TaskLabelIden::Table)
+			.and_where(Expr::col(TaskLabelIden::TaskId).eq(task_id))
+			.to_owned();
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(<Label as modql::field::HasFields>::field_column_refs())
+			.and_where(Expr::col(LabelIden::Id).in_subquery(label_ids))
+			.order_by(LabelIden::Id, sea_query::Order::Asc);
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, Label, _>(&sql, values);
+		let labels = dbx.fetch_all(sqlx_query).await?;
+
+		Ok(labels)
+	}
+
+	pub async fn attach(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task_id: i64,
+		label_id: i64,
+	) -> Result<()> {
+		let dbx = mm.dbx();
+
+		TaskBmc::get(ctx, mm, task_id).await?;
+		Self::get(ctx, mm, label_id).await?;
+
+		// build query
+		let mut query = Query::insert();
+		query
+			.into_table(TaskLabelIden::Table)
+			.columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+			.values([task_id.into(), label_id.into()])?
+			.on_conflict(
+				OnConflict::columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+					.do_nothing()
+					.to_owned(),
+			);
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_with(&sql, values);
+		dbx.execute(sqlx_query).await?;
+
+		Ok(())
+	}
+
+	pub async fn detach(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task_id: i64,
+		label_id: i64,
+	) -> Result<()> {
+		let dbx = mm.dbx();
+
+		// build query
+		let mut query = Query::delete();
+		query
+			.from_table(TaskLabelIden::Table)
+			.and_where(Expr::col(TaskLabelIden::TaskId).eq(task_id))
+			.and_where(Expr::col(TaskLabelIden::LabelId).eq(label_id));
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_with(&sql, values);
+		dbx.execute(sqlx_query).await?;
+
+		Ok(())
+	}
+}
+
+// region:    --- Task Label Sub Queries
+
+/// `SELECT task_id` of the tasks having at least one of the `label_ids`.
+pub(in crate::model) fn select_task_ids_with_any_labels(
+	label_ids: &[i64],
+) -> SelectStatement {
+	Query::select()
+		.distinct()
+		.column(TaskLabelIden::TaskId)
+		.from(TaskLabelIden::Table)
+		.and_where(Expr::col(TaskLabelIden::LabelId).is_in(label_ids.to_vec()))
+		.to_owned()
+}
+
+/// `SELECT task_id` of the tasks having all of the `label_ids`.
+pub(in crate::model) fn select_task_ids_with_all_labels(
+	label_ids: &[i64],
+) -> SelectStatement {
+	let mut label_ids = label_ids.to_vec();
+	label_ids.sort_unstable();
+	label_ids.dedup();
+	let count = label_ids.len() as i64;
+
+	Query::select()
+		.column(TaskLabelIden::TaskId)
+		.from(TaskLabelIden::Table)
+		.and_where(Expr::col(TaskLabelIden::LabelId).is_in(label_ids))
+		.group_by_col(TaskLabelIden::TaskId)
+		.and_having(
+			Expr::expr(Expr::col(TaskLabelIden::LabelId).count_distinct()).eq(count),
+		)
+		.to_owned()
+}
+
+// endregion: --- Task Label Sub Queries
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::TaskFilter;
+	use anyhow::Result;
+	use serde_json::json;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_label_attach_and_filter_tasks() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&[
+				"test_label_task_1",
+				"test_label_task_2",
+				"test_label_task_3",
+			],
+		)
+		.await?;
+		let mut labels = Vec::new();
+		for name in ["test_label_bug", "test_label_urgent"] {
+			let label = LabelBmc::create(
+				&ctx,
+				&mm,
+				LabelForCreate {
+					name: name.to_string(),
+					..Default::default()
+				},
+			)
+			.await?;
+			labels.push(label);
+		}
+		let (bug, urgent) = (labels[0].id, labels[1].id);
+
+		// task_1: bug, urgent | task_2: bug | task_3: none
+		LabelBmc::attach(&ctx, &mm, tasks[0].id, bug).await?;
+		LabelBmc::attach(&ctx, &mm, tasks[0].id, urgent).await?;
+		LabelBmc::attach(&ctx, &mm, tasks[1].id, bug).await?;
+
+		// Check list_for_task
+		let task_labels = LabelBmc::list_for_task(&ctx, &mm, tasks[0].id).await?;
+		assert_eq!(task_labels.len(), 2, "labels of task_1");
+
+		// Check has any
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_label_task_"},
+			"labels_any": [bug, urgent],
+		}))?;
+		let found = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+		let found: Vec<i64> = found.into_iter().map(|t| t.id).collect();
+		assert_eq!(
+			found,
+			vec![tasks[0].id, tasks[1].id],
+			"tasks with any labels"
+		);
+
+		// Check has all
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_label_task_"},
+			"labels_all": [bug, urgent],
+		}))?;
+		let found = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+		let found: Vec<i64> = found.into_iter().map(|t| t.id).collect();
+		assert_eq!(found, vec![tasks[0].id], "tasks with all labels");
+
+		// Check empty label lists (no condition)
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_label_task_"},
+			"labels_any": [],
+			"labels_all": [],
+		}))?;
+		let found = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+		assert_eq!(found.len(), tasks.len(), "tasks with empty label lists");
+
+		// Check detach
+		LabelBmc::detach(&ctx, &mm, tasks[0].id, urgent).await?;
+		let task_labels = LabelBmc::list_for_task(&ctx, &mm, tasks[0].id).await?;
+		assert_eq!(task_labels.len(), 1, "labels of task_1 after detach");
+
+		// Cleanup
+		for task in tasks {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+		for label in labels {
+			LabelBmc::delete(&ctx, &mm, label.id).await?;
+		}
+
+		Ok(())
+	}
+}
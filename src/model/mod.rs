@@ -1,28 +1,137 @@
 // region:    --- Modules
 
 mod base;
+pub mod comment;
+pub mod entity_history;
 mod error;
+pub mod label;
+mod modql_utils;
+pub mod page;
 mod store;
 pub mod task;
 pub mod user;
+pub mod user_profile;
 
-pub use self::error::{Error, Result};
-use self::store::{new_db_pool, Db};
+pub use self::error::{BulkItemError, Error, Result};
+pub use self::store::migration;
+use self::store::dbx::Dbx;
+use self::store::new_db_pool;
+use serde::Serialize;
 
 // endregion: --- Modules
 
+/// See `ModelManager::db_status`.
+#[derive(Debug, Serialize)]
+pub struct DbStatus {
+	pub migrations: migration::MigrationStatus,
+	pub pool: PoolStatus,
+}
+
+/// The connections of the db pool (see `ModelManager::pool_status`).
+#[derive(Debug, Serialize)]
+pub struct PoolStatus {
+	/// Open connections, in use or idle.
+	pub size: u32,
+	pub idle: usize,
+	pub max: u32,
+}
+
 #[derive(Clone)]
 pub struct ModelManager {
-	db: Db,
+	dbx: Dbx,
 }
 
 impl ModelManager {
 	pub async fn new() -> Result<Self> {
 		let db = new_db_pool().await?;
-		Ok(ModelManager { db })
+		let dbx = Dbx::new(db, false);
+		Ok(ModelManager { dbx })
+	}
+
+	/// A ModelManager sharing the same pool, on which `begin_txn` opens a
+	/// transaction that all the Bmc calls made with it go through, until
+	/// `commit_txn` (or `rollback_txn`), see `end_txn`. Dropping it before the
+	/// commit rolls the transaction back.
+	///
+	/// Returns a clone of `self` if it is already transactional, so nested
+	/// calls join the outer transaction.
+	pub fn new_with_txn(&self) -> ModelManager {
+		if self.dbx.with_txn() {
+			return self.clone();
+		}
+		let dbx = Dbx::new(self.dbx.db().clone(), true);
+		ModelManager { dbx }
+	}
+
+	pub async fn begin_txn(&self) -> Result<()> {
+		Ok(self.dbx.begin_txn().await?)
+	}
+
+	pub async fn commit_txn(&self) -> Result<()> {
+		Ok(self.dbx.commit_txn().await?)
+	}
+
+	pub async fn rollback_txn(&self) -> Result<()> {
+		Ok(self.dbx.rollback_txn().await?)
 	}
 
-  pub(in crate::model) fn db(&self) -> &Db {
-    &self.db
-  }
+	/// Ends the transaction begun with `begin_txn` given the `res` of the work
+	/// done in it: commits if ok, rolls back otherwise (and returns `res`).
+	///
+	/// Usage: `let res = async { ... }.await; mm.end_txn(res).await`, so that
+	/// an early `?` in the work cannot leave the transaction open.
+	pub async fn end_txn<T, E>(
+		&self,
+		res: core::result::Result<T, E>,
+	) -> core::result::Result<T, E>
+	where
+		E: From<Error>,
+	{
+		match res {
+			Ok(val) => {
+				self.commit_txn().await?;
+				Ok(val)
+			}
+			Err(err) => {
+				self.rollback_txn().await?;
+				Err(err)
+			}
+		}
+	}
+
+	/// Applies the pending schema migrations, see `migration::migrate`.
+	pub async fn migrate(&self) -> Result<Vec<i64>> {
+		Ok(migration::migrate(self.dbx.db()).await?)
+	}
+
+	/// Checks the db is reachable (`SELECT 1`), with the migration status and
+	/// the pool statistics.
+	pub async fn db_status(&self) -> Result<DbStatus> {
+		let db = self.dbx.db();
+		sqlx::query("SELECT 1").execute(db).await?;
+		let migrations = migration::status(db).await?;
+
+		Ok(DbStatus {
+			migrations,
+			pool: self.pool_status(),
+		})
+	}
+
+	pub fn pool_status(&self) -> PoolStatus {
+		let db = self.dbx.db();
+		PoolStatus {
+			size: db.size(),
+			idle: db.num_idle(),
+			max: db.options().get_max_connections(),
+		}
+	}
+
+	/// Closes the db pool, waiting for the connections in use to be returned.
+	pub async fn close(&self) {
+		self.dbx.db().close().await;
+	}
+
+	pub(in crate::model) fn dbx(&self) -> &Dbx {
+		&self.dbx
+	}
 }
@@ -0,0 +1,17 @@
+use modql::filter::{IntoSeaError, SeaResult};
+
+use crate::utils::parse_utc_str;
+
+/// modql `to_sea_value_fn` for `OpValsValue` filters on timestamp columns.
+/// The json value must be a Rfc3339 string (e.g., `{"$gte": "2024-01-01T00:00:00Z"}`).
+pub fn time_to_sea_value(
+	json_value: serde_json::Value,
+) -> SeaResult<sea_query::Value> {
+	let time_str = json_value.as_str().ok_or_else(|| {
+		IntoSeaError::custom(format!("time value should be a string: {json_value}"))
+	})?;
+	let time = parse_utc_str(time_str)
+		.map_err(|err| IntoSeaError::custom(err.to_string()))?;
+
+	Ok(time.into())
+}
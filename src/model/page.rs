@@ -0,0 +1,105 @@
+//! Keyset (cursor) pagination for the list APIs.
+//!
+//! Pages are walked by ascending `id`. Cursors are opaque to the clients
+//! (base64url of the direction and the boundary id).
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+use crate::utils::{b64u_decode, b64u_encode};
+
+/// Entities that can be paged by id.
+pub trait HasId {
+	fn id(&self) -> i64;
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PageOptions {
+	/// The `next_cursor` or `prev_cursor` of a previous page.
+	pub cursor: Option<String>,
+	/// When true, the `Page` includes the total number of matching items.
+	#[serde(default)]
+	pub with_total: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<E> {
+	pub items: Vec<E>,
+	pub next_cursor: Option<String>,
+	pub prev_cursor: Option<String>,
+	pub total: Option<i64>,
+}
+
+impl<E> Page<E> {
+	/// Builds the page from `limit + 1` fetched items (the extra one only
+	/// tells that there are more items in the `cursor` direction).
+	pub(in crate::model) fn from_fetched(
+		mut items: Vec<E>,
+		limit: usize,
+		cursor: Option<&Cursor>,
+		total: Option<i64>,
+	) -> Self
+	where
+		E: HasId,
+	{
+		let has_more = items.len() > limit;
+		items.truncate(limit);
+
+		let first_id = items.first().map(HasId::id);
+		let last_id = items.last().map(HasId::id);
+		let (next_id, prev_id) = match cursor {
+			// Fetched in descending order, back to ascending.
+			Some(Cursor::Prev(_)) => {
+				items.reverse();
+				(first_id, has_more.then_some(last_id).flatten())
+			}
+			Some(Cursor::Next(_)) => {
+				(has_more.then_some(last_id).flatten(), first_id)
+			}
+			None => (has_more.then_some(last_id).flatten(), None),
+		};
+
+		Page {
+			items,
+			next_cursor: next_id.map(|id| Cursor::Next(id).encode()),
+			prev_cursor: prev_id.map(|id| Cursor::Prev(id).encode()),
+			total,
+		}
+	}
+}
+
+// region:    --- Cursor
+
+/// Items after (`Next`) or before (`Prev`) the item with this id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(in crate::model) enum Cursor {
+	Next(i64),
+	Prev(i64),
+}
+
+impl Cursor {
+	pub fn encode(&self) -> String {
+		let content = match self {
+			Cursor::Next(id) => format!("next:{id}"),
+			Cursor::Prev(id) => format!("prev:{id}"),
+		};
+		b64u_encode(&content)
+	}
+
+	pub fn decode(cursor: &str) -> Result<Cursor> {
+		let invalid = || Error::InvalidCursor {
+			cursor: cursor.to_string(),
+		};
+		let content = b64u_decode(cursor).map_err(|_| invalid())?;
+		let (dir, id) = content.split_once(':').ok_or_else(invalid)?;
+		let id: i64 = id.parse().map_err(|_| invalid())?;
+
+		match dir {
+			"next" => Ok(Cursor::Next(id)),
+			"prev" => Ok(Cursor::Prev(id)),
+			_ => Err(invalid()),
+		}
+	}
+}
+
+// endregion: --- Cursor
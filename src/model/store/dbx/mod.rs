@@ -0,0 +1,239 @@
+//! Database executor that runs the queries either on the pool or, when
+//! created `with_txn` and a transaction was begun, on that transaction.
+//!
+//! Transactions can be nested (e.g., a Bmc function beginning a transaction
+//! called within a rpc transaction); the nested levels are savepoints, so
+//! only the outermost `commit_txn` commits, and a nested `rollback_txn` only
+//! rolls back its own level.
+//!
+//! A `with_txn` Dbx never falls back to the pool: its queries fail with
+//! `Error::TxnNoOpenTxn` when no transaction is open.
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::sync::Arc;
+
+use sqlx::postgres::PgRow;
+use sqlx::query::{Query, QueryAs};
+use sqlx::{Execute, FromRow, IntoArguments, Postgres, Transaction};
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use super::Db;
+
+// endregion: --- Modules
+
+#[derive(Debug, Clone)]
+pub struct Dbx {
+	db_pool: Db,
+	txn_holder: Arc<Mutex<Option<TxnHolder>>>,
+	with_txn: bool,
+}
+
+#[derive(Debug)]
+struct TxnHolder {
+	txn: Transaction<'static, Postgres>,
+	counter: i32,
+}
+
+impl Dbx {
+	pub fn new(db_pool: Db, with_txn: bool) -> Self {
+		Dbx {
+			db_pool,
+			txn_holder: Arc::default(),
+			with_txn,
+		}
+	}
+
+	pub fn db(&self) -> &Db {
+		&self.db_pool
+	}
+
+	pub fn with_txn(&self) -> bool {
+		self.with_txn
+	}
+}
+
+// region:    --- Txn
+
+impl Dbx {
+	/// Begins the transaction, or a savepoint of it when already begun.
+	pub async fn begin_txn(&self) -> Result<()> {
+		if !self.with_txn {
+			return Err(Error::TxnCantBeginWithTxnFalse);
+		}
+
+		let mut txh_g = self.txn_holder.lock().await;
+		match txh_g.as_mut() {
+			Some(txh) => {
+				txh.counter += 1;
+				let sql = format!("SAVEPOINT {}", savepoint_name(txh.counter));
+				sqlx::query(&sql).execute(&mut *txh.txn).await?;
+			}
+			None => {
+				let txn = self.db_pool.begin().await?;
+				*txh_g = Some(TxnHolder { txn, counter: 1 });
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Commits the transaction, or releases the current savepoint when nested.
+	pub async fn commit_txn(&self) -> Result<()> {
+		if !self.with_txn {
+			return Err(Error::TxnCantCommitWithTxnFalse);
+		}
+
+		let mut txh_g = self.txn_holder.lock().await;
+		let Some(txh) = txh_g.as_mut() else {
+			return Err(Error::TxnCantCommitNoOpenTxn);
+		};
+
+		if txh.counter > 1 {
+			let sql = format!("RELEASE SAVEPOINT {}", savepoint_name(txh.counter));
+			sqlx::query(&sql).execute(&mut *txh.txn).await?;
+			txh.counter -= 1;
+		} else if let Some(txh) = txh_g.take() {
+			txh.txn.commit().await?;
+		}
+
+		Ok(())
+	}
+
+	/// Rolls back the transaction, or only to the current savepoint when
+	/// nested (the outer transaction stays open).
+	pub async fn rollback_txn(&self) -> Result<()> {
+		if !self.with_txn {
+			return Err(Error::TxnCantRollbackWithTxnFalse);
+		}
+
+		let mut txh_g = self.txn_holder.lock().await;
+		let Some(txh) = txh_g.as_mut() else {
+			return Err(Error::TxnCantRollbackNoOpenTxn);
+		};
+
+		if txh.counter > 1 {
+			let name = savepoint_name(txh.counter);
+			let sql = format!("ROLLBACK TO SAVEPOINT {name}");
+			sqlx::query(&sql).execute(&mut *txh.txn).await?;
+			let sql = format!("RELEASE SAVEPOINT {name}");
+			sqlx::query(&sql).execute(&mut *txh.txn).await?;
+			txh.counter -= 1;
+		} else if let Some(txh) = txh_g.take() {
+			txh.txn.rollback().await?;
+		}
+
+		Ok(())
+	}
+}
+
+fn savepoint_name(level: i32) -> String {
+	format!("dbx_savepoint_{level}")
+}
+
+// endregion: --- Txn
+
+// region:    --- Executors
+
+/// Note: Each query runs within a `db.query` span, with its sql statement.
+impl Dbx {
+	#[instrument(
+		name = "db.query",
+		skip_all,
+		fields(db.system = "postgresql", db.statement = %query.sql())
+	)]
+	pub async fn fetch_one<'q, O, A>(
+		&self,
+		query: QueryAs<'q, Postgres, O, A>,
+	) -> Result<O>
+	where
+		O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+		A: IntoArguments<'q, Postgres> + Send + 'q,
+	{
+		if self.with_txn {
+			let mut txh_g = self.txn_holder.lock().await;
+			let Some(txh) = txh_g.as_mut() else {
+				return Err(Error::TxnNoOpenTxn);
+			};
+			return Ok(query.fetch_one(&mut *txh.txn).await?);
+		}
+
+		Ok(query.fetch_one(self.db()).await?)
+	}
+
+	#[instrument(
+		name = "db.query",
+		skip_all,
+		fields(db.system = "postgresql", db.statement = %query.sql())
+	)]
+	pub async fn fetch_optional<'q, O, A>(
+		&self,
+		query: QueryAs<'q, Postgres, O, A>,
+	) -> Result<Option<O>>
+	where
+		O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+		A: IntoArguments<'q, Postgres> + Send + 'q,
+	{
+		if self.with_txn {
+			let mut txh_g = self.txn_holder.lock().await;
+			let Some(txh) = txh_g.as_mut() else {
+				return Err(Error::TxnNoOpenTxn);
+			};
+			return Ok(query.fetch_optional(&mut *txh.txn).await?);
+		}
+
+		Ok(query.fetch_optional(self.db()).await?)
+	}
+
+	#[instrument(
+		name = "db.query",
+		skip_all,
+		fields(db.system = "postgresql", db.statement = %query.sql())
+	)]
+	pub async fn fetch_all<'q, O, A>(
+		&self,
+		query: QueryAs<'q, Postgres, O, A>,
+	) -> Result<Vec<O>>
+	where
+		O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+		A: IntoArguments<'q, Postgres> + Send + 'q,
+	{
+		if self.with_txn {
+			let mut txh_g = self.txn_holder.lock().await;
+			let Some(txh) = txh_g.as_mut() else {
+				return Err(Error::TxnNoOpenTxn);
+			};
+			return Ok(query.fetch_all(&mut *txh.txn).await?);
+		}
+
+		Ok(query.fetch_all(self.db()).await?)
+	}
+
+	/// Returns the number of rows affected.
+	#[instrument(
+		name = "db.query",
+		skip_all,
+		fields(db.system = "postgresql", db.statement = %query.sql())
+	)]
+	pub async fn execute<'q, A>(&self, query: Query<'q, Postgres, A>) -> Result<u64>
+	where
+		A: IntoArguments<'q, Postgres> + Send + 'q,
+	{
+		if self.with_txn {
+			let mut txh_g = self.txn_holder.lock().await;
+			let Some(txh) = txh_g.as_mut() else {
+				return Err(Error::TxnNoOpenTxn);
+			};
+			return Ok(query.execute(&mut *txh.txn).await?.rows_affected());
+		}
+
+		Ok(query.execute(self.db()).await?.rows_affected())
+	}
+}
+
+// endregion: --- Executors
@@ -1,10 +1,22 @@
 use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+#[serde_as]
 #[derive(Debug, Serialize)]
 pub enum Error {
-	FailToCreatePool(String),
+	/// The `SERVICE_DB_URL` cannot be parsed.
+	InvalidDbUrl(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+	/// The db was still unreachable (io error or timeout) after `attempts`.
+	DbUnreachable {
+		attempts: u32,
+		#[serde_as(as = "DisplayFromStr")]
+		cause: sqlx::Error,
+	},
+	/// The db was reached but refused the connection (e.g., wrong password,
+	/// unknown database), not retried.
+	DbConnectFailed(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
 }
 
 // region:    --- Error Boilerplate
@@ -0,0 +1,49 @@
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+	/// The db has a migration this binary does not know (newer binary
+	/// deployed then rolled back?), refusing to run against it.
+	DbAhead {
+		version: i64,
+	},
+	/// An applied migration file was edited afterward.
+	ChecksumMismatch {
+		version: i64,
+		name: String,
+	},
+	/// The statement `index` (0 based) of the migration `version` failed.
+	MigrationFailed {
+		version: i64,
+		name: String,
+		index: usize,
+		#[serde_as(as = "DisplayFromStr")]
+		cause: sqlx::Error,
+	},
+
+	// -- Externals
+	Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+impl From<sqlx::Error> for Error {
+	fn from(val: sqlx::Error) -> Self {
+		Self::Sqlx(val)
+	}
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
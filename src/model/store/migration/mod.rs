@@ -0,0 +1,258 @@
+//! Versioned schema migrations, embedded from `sql/migrations`.
+//!
+//! Each applied migration is recorded in `schema_migrations` with the
+//! checksum of its sql, so an applied migration that was edited afterward,
+//! or a db migrated by a newer binary, is refused rather than silently run
+//! against.
+//!
+//! Note: All the pending migrations are applied in one transaction, under
+//! an advisory lock, so concurrent instances starting up apply them once.
+
+// region:    --- Modules
+
+mod error;
+mod sql_split;
+
+pub use self::error::{Error, Result};
+pub use self::sql_split::split_sql;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgConnection};
+use tracing::info;
+
+use super::Db;
+
+// endregion: --- Modules
+
+/// The migrations, by strictly increasing version. To add one, add the
+/// `sql/migrations/<version>_<name>.sql` file and its entry here.
+const MIGRATIONS: &[Migration] = &[Migration {
+	version: 1,
+	name: "initial_schema",
+	sql: include_str!("../../../../sql/migrations/0001_initial_schema.sql"),
+}];
+
+const SQL_CREATE_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS schema_migrations (
+  version BIGINT PRIMARY KEY,
+  name varchar(256) NOT NULL,
+  checksum varchar(64) NOT NULL,
+  applied_at timestamp with time zone NOT NULL DEFAULT now()
+)"#;
+
+/// The `pg_advisory_xact_lock` key of the migrations.
+const LOCK_KEY_MIGRATION: i64 = 0x6d69_6772;
+
+pub struct Migration {
+	pub version: i64,
+	pub name: &'static str,
+	pub sql: &'static str,
+}
+
+impl Migration {
+	/// The sha256 (hex) of the sql.
+	pub fn checksum(&self) -> String {
+		format!("{:x}", Sha256::digest(self.sql.as_bytes()))
+	}
+}
+
+#[derive(FromRow)]
+struct AppliedMigration {
+	version: i64,
+	checksum: String,
+}
+
+/// The migration versions applied to the db, or not.
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+	pub applied: Vec<i64>,
+	pub pending: Vec<i64>,
+	/// Applied, but not known of this binary (see `Error::DbAhead`).
+	pub unknown: Vec<i64>,
+}
+
+impl MigrationStatus {
+	pub fn is_up_to_date(&self) -> bool {
+		self.pending.is_empty() && self.unknown.is_empty()
+	}
+}
+
+/// Note: Does not verify the checksums (see `migrate`).
+pub async fn status(db: &Db) -> Result<MigrationStatus> {
+	let mut conn = db.acquire().await?;
+	status_on(&mut conn).await
+}
+
+/// Note: No `schema_migrations` table yet (never migrated) is every
+///       migration pending.
+async fn status_on(conn: &mut PgConnection) -> Result<MigrationStatus> {
+	let has_table: bool = sqlx::query_scalar(
+		"SELECT to_regclass('schema_migrations') IS NOT NULL",
+	)
+	.fetch_one(&mut *conn)
+	.await?;
+	let applied: Vec<i64> = match has_table {
+		true => {
+			sqlx::query_scalar(
+				"SELECT version FROM schema_migrations ORDER BY version",
+			)
+			.fetch_all(&mut *conn)
+			.await?
+		}
+		false => Vec::new(),
+	};
+	let pending = MIGRATIONS
+		.iter()
+		.map(|m| m.version)
+		.filter(|v| !applied.contains(v))
+		.collect();
+	let unknown = applied
+		.iter()
+		.copied()
+		.filter(|v| !MIGRATIONS.iter().any(|m| m.version == *v))
+		.collect();
+
+	Ok(MigrationStatus {
+		applied,
+		pending,
+		unknown,
+	})
+}
+
+/// Applies the pending migrations, returns their versions.
+pub async fn migrate(db: &Db) -> Result<Vec<i64>> {
+	let mut txn = db.begin().await?;
+	sqlx::query("SELECT pg_advisory_xact_lock($1)")
+		.bind(LOCK_KEY_MIGRATION)
+		.execute(&mut *txn)
+		.await?;
+	sqlx::query(SQL_CREATE_TABLE).execute(&mut *txn).await?;
+
+	// -- Check the applied ones
+	let applied: Vec<AppliedMigration> = sqlx::query_as(
+		"SELECT version, checksum FROM schema_migrations ORDER BY version",
+	)
+	.fetch_all(&mut *txn)
+	.await?;
+	for applied in applied.iter() {
+		let migration = MIGRATIONS
+			.iter()
+			.find(|m| m.version == applied.version)
+			.ok_or(Error::DbAhead {
+				version: applied.version,
+			})?;
+		if migration.checksum() != applied.checksum {
+			return Err(Error::ChecksumMismatch {
+				version: migration.version,
+				name: migration.name.to_string(),
+			});
+		}
+	}
+
+	// -- Apply the pending ones
+	let mut versions = Vec::new();
+	for migration in MIGRATIONS
+		.iter()
+		.filter(|m| !applied.iter().any(|a| a.version == m.version))
+	{
+		info!(
+			"{:<12} - {:04}_{}",
+			"MIGRATE", migration.version, migration.name
+		);
+		for (index, sql) in split_sql(migration.sql).into_iter().enumerate() {
+			sqlx::query(sql).execute(&mut *txn).await.map_err(|cause| {
+				Error::MigrationFailed {
+					version: migration.version,
+					name: migration.name.to_string(),
+					index,
+					cause,
+				}
+			})?;
+		}
+		sqlx::query(
+			"INSERT INTO schema_migrations (version, name, checksum) \
+			 VALUES ($1, $2, $3)",
+		)
+		.bind(migration.version)
+		.bind(migration.name)
+		.bind(migration.checksum())
+		.execute(&mut *txn)
+		.await?;
+		versions.push(migration.version);
+	}
+
+	txn.commit().await?;
+
+	Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+	use std::fs;
+
+	#[test]
+	fn test_migrations_match_files() -> Result<()> {
+		let mut files: Vec<String> = fs::read_dir("sql/migrations")?
+			.filter_map(|entry| entry.ok())
+			.map(|entry| entry.file_name().to_string_lossy().to_string())
+			.filter(|name| name.ends_with(".sql"))
+			.collect();
+		files.sort();
+
+		let names: Vec<String> = MIGRATIONS
+			.iter()
+			.map(|m| format!("{:04}_{}.sql", m.version, m.name))
+			.collect();
+		assert_eq!(names, files);
+		assert!(
+			MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version),
+			"versions strictly increasing"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_migrate_up_to_date() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+
+		// -- Exec (the dev db init already migrated)
+		let versions = mm.migrate().await?;
+
+		// -- Check
+		assert!(versions.is_empty(), "nothing pending: {versions:?}");
+		let status = status(mm.dbx().db()).await?;
+		assert!(status.is_up_to_date(), "status: {status:?}");
+		assert_eq!(status.applied, [1]);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_status_never_migrated() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let mut txn = mm.dbx().db().begin().await?;
+		sqlx::query("DROP TABLE schema_migrations")
+			.execute(&mut *txn)
+			.await?;
+
+		// -- Exec
+		let status = status_on(&mut txn).await?;
+
+		// -- Check
+		assert!(status.applied.is_empty(), "status: {status:?}");
+		assert_eq!(status.pending, [1]);
+		assert!(!status.is_up_to_date());
+
+		// -- Clean (the drop is rolled back)
+		txn.rollback().await?;
+
+		Ok(())
+	}
+}
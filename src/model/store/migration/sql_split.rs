@@ -0,0 +1,200 @@
+//! Splits a sql script into its statements, on the `;` that are not within
+//! a string, quoted identifier, comment or dollar-quoted body.
+
+/// The statements of the `sql` script (without their `;`, trimmed), the
+/// empty (or comment only) ones skipped.
+pub fn split_sql(sql: &str) -> Vec<&str> {
+	let bytes = sql.as_bytes();
+	let mut statements = Vec::new();
+	let mut start = 0;
+	let mut has_code = false;
+	let mut i = 0;
+
+	while i < bytes.len() {
+		let end = match bytes[i] {
+			b'\'' => {
+				let escapes = is_escape_string_prefix(bytes, i);
+				skip_quoted(bytes, i, b'\'', escapes)
+			}
+			b'"' => skip_quoted(bytes, i, b'"', false),
+			b'-' if bytes.get(i + 1) == Some(&b'-') => skip_line_comment(bytes, i),
+			b'/' if bytes.get(i + 1) == Some(&b'*') => skip_block_comment(bytes, i),
+			b'$' => match dollar_tag(bytes, i) {
+				Some(tag) => skip_dollar_quoted(sql, i, tag),
+				None => i + 1,
+			},
+			b';' => {
+				if has_code {
+					statements.push(sql[start..i].trim());
+				}
+				start = i + 1;
+				has_code = false;
+				i += 1;
+				continue;
+			}
+			_ => i + 1,
+		};
+
+		// comments do not make a statement
+		let is_comment = matches!(
+			(bytes[i], bytes.get(i + 1)),
+			(b'-', Some(b'-')) | (b'/', Some(b'*'))
+		);
+		if !is_comment && !bytes[i].is_ascii_whitespace() {
+			has_code = true;
+		}
+		i = end;
+	}
+
+	if has_code {
+		statements.push(sql[start..].trim());
+	}
+
+	statements
+}
+
+// region:    --- Support
+
+/// Is the quote at `i` opening an `E'...'` string (backslash escapes).
+fn is_escape_string_prefix(bytes: &[u8], i: usize) -> bool {
+	match i.checked_sub(1).map(|p| bytes[p]) {
+		Some(b'e' | b'E') => i < 2 || !is_ident_byte(bytes[i - 2]),
+		_ => false,
+	}
+}
+
+/// The index after the closing `quote` of the string opened at `i`
+/// (a doubled quote being an escaped one).
+fn skip_quoted(bytes: &[u8], i: usize, quote: u8, escapes: bool) -> usize {
+	let mut j = i + 1;
+	while j < bytes.len() {
+		match bytes[j] {
+			b'\\' if escapes => j += 2,
+			b if b == quote => {
+				if bytes.get(j + 1) == Some(&quote) {
+					j += 2;
+				} else {
+					return j + 1;
+				}
+			}
+			_ => j += 1,
+		}
+	}
+	bytes.len()
+}
+
+fn skip_line_comment(bytes: &[u8], i: usize) -> usize {
+	bytes[i..]
+		.iter()
+		.position(|b| *b == b'\n')
+		.map_or(bytes.len(), |p| i + p + 1)
+}
+
+/// Note: Postgres block comments nest.
+fn skip_block_comment(bytes: &[u8], i: usize) -> usize {
+	let mut depth = 0;
+	let mut j = i;
+	while j < bytes.len() {
+		match (bytes[j], bytes.get(j + 1)) {
+			(b'/', Some(b'*')) => {
+				depth += 1;
+				j += 2;
+			}
+			(b'*', Some(b'/')) => {
+				depth -= 1;
+				j += 2;
+				if depth == 0 {
+					return j;
+				}
+			}
+			_ => j += 1,
+		}
+	}
+	bytes.len()
+}
+
+/// The `$tag$` (or `$$`) opening a dollar-quoted string at `i`, if any
+/// (`$1` being a parameter and `a$b` an identifier).
+fn dollar_tag(bytes: &[u8], i: usize) -> Option<&[u8]> {
+	if i > 0 && is_ident_byte(bytes[i - 1]) {
+		return None;
+	}
+	let mut j = i + 1;
+	while j < bytes.len() && bytes[j] != b'$' {
+		let valid = if j == i + 1 {
+			bytes[j].is_ascii_alphabetic() || bytes[j] == b'_'
+		} else {
+			is_ident_byte(bytes[j])
+		};
+		if !valid {
+			return None;
+		}
+		j += 1;
+	}
+	(j < bytes.len()).then(|| &bytes[i..=j])
+}
+
+fn skip_dollar_quoted(sql: &str, i: usize, tag: &[u8]) -> usize {
+	let body = i + tag.len();
+	let tag = std::str::from_utf8(tag).unwrap_or("$$");
+	sql[body..]
+		.find(tag)
+		.map_or(sql.len(), |p| body + p + tag.len())
+}
+
+fn is_ident_byte(b: u8) -> bool {
+	b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+// endregion: --- Support
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_split_sql_simple() {
+		let sqls = split_sql("SELECT 1;\n  SELECT 2 ;\n\n;SELECT 3");
+		assert_eq!(sqls, ["SELECT 1", "SELECT 2", "SELECT 3"]);
+	}
+
+	#[test]
+	fn test_split_sql_strings_and_identifiers() {
+		let sqls = split_sql(
+			r#"INSERT INTO "a;b" VALUES ('x;''y', E'it\'s;', 'c:\');
+			SELECT 2;"#,
+		);
+		assert_eq!(
+			sqls,
+			[
+				r#"INSERT INTO "a;b" VALUES ('x;''y', E'it\'s;', 'c:\')"#,
+				"SELECT 2"
+			]
+		);
+	}
+
+	#[test]
+	fn test_split_sql_comments() {
+		let sqls = split_sql(
+			"-- first; not a split\nSELECT 1; /* a; /* nested; */ b; */\n-- end;",
+		);
+		assert_eq!(sqls, ["-- first; not a split\nSELECT 1"]);
+	}
+
+	#[test]
+	fn test_split_sql_dollar_quoted() {
+		let sqls = split_sql(
+			"CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$; $body$ \
+			 LANGUAGE sql; DO $$ BEGIN PERFORM 1; END $$; SELECT $1, a$b;",
+		);
+		assert_eq!(
+			sqls,
+			[
+				"CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$; $body$ \
+				 LANGUAGE sql",
+				"DO $$ BEGIN PERFORM 1; END $$",
+				"SELECT $1, a$b"
+			]
+		);
+	}
+}
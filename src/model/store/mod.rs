@@ -1,8 +1,15 @@
 // start-region:    --- Modules
 
+pub(in crate::model) mod dbx;
 mod error;
+pub mod migration;
 
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::str::FromStr;
+use std::time::Duration;
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Pool, Postgres};
+use tracing::warn;
 
 pub use self::error::{Error, Result};
 
@@ -12,10 +19,62 @@ use crate::config;
 
 pub type Db = Pool<Postgres>;
 
+/// The max delay between two connection attempts.
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Creates the pool (tuned by the `DB_*` config), retrying with backoff
+/// while the db is not reachable yet (e.g., starting along with the service).
 pub async fn new_db_pool() -> Result<Db> {
-	PgPoolOptions::new()
-		.max_connections(5)
-		.connect(&config().DB_URL)
-		.await
-		.map_err(|err| Error::FailToCreatePool(err.to_string()))
+	let config = config();
+	let connect_options =
+		PgConnectOptions::from_str(&config.DB_URL).map_err(Error::InvalidDbUrl)?;
+	let connect_options = match config.DB_STATEMENT_TIMEOUT_MS {
+		0 => connect_options,
+		ms => connect_options.options([("statement_timeout", ms.to_string())]),
+	};
+	let pool_options = PgPoolOptions::new()
+		.min_connections(config.DB_MIN_CONNECTIONS)
+		.max_connections(config.DB_MAX_CONNECTIONS)
+		.acquire_timeout(Duration::from_millis(config.DB_ACQUIRE_TIMEOUT_MS))
+		.idle_timeout(secs_or_none(config.DB_IDLE_TIMEOUT_SEC))
+		.max_lifetime(secs_or_none(config.DB_MAX_LIFETIME_SEC));
+
+	let mut delay = Duration::from_millis(config.DB_CONNECT_RETRY_DELAY_MS);
+	let mut attempts = 1;
+	loop {
+		let res = pool_options
+			.clone()
+			.connect_with(connect_options.clone())
+			.await;
+		match res {
+			Ok(db) => return Ok(db),
+			Err(err) if !is_unreachable(&err) => {
+				return Err(Error::DbConnectFailed(err));
+			}
+			Err(err) if attempts >= config.DB_CONNECT_ATTEMPTS => {
+				return Err(Error::DbUnreachable {
+					attempts,
+					cause: err,
+				});
+			}
+			Err(err) => {
+				warn!(
+					"{:<12} - attempt {attempts} failed, retry in {delay:?} - {err}",
+					"DB CONNECT"
+				);
+				tokio::time::sleep(delay).await;
+				delay = (delay * 2).min(CONNECT_RETRY_MAX_DELAY);
+				attempts += 1;
+			}
+		}
+	}
+}
+
+fn is_unreachable(err: &sqlx::Error) -> bool {
+	matches!(err, sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)
+}
+
+/// Note: 0 means none (e.g., no idle timeout).
+fn secs_or_none(secs: u64) -> Option<Duration> {
+	(secs > 0).then(|| Duration::from_secs(secs))
 }
@@ -1,31 +1,220 @@
 use modql::{
-	field::Fields,
-	filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString},
+	field::{Field, Fields, HasFields},
+	filter::{
+		FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString, OpValsValue,
+	},
 };
+use sea_query::{
+	Alias, Asterisk, ColumnRef, Condition, DynIden, Expr, Iden, IntoIden, Keyword,
+	OnConflict, Order, PostgresQueryBuilder, Query, SimpleExpr,
+};
+use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sqlx::prelude::FromRow;
+use std::collections::HashMap;
+use time::{Duration, OffsetDateTime};
 
+use super::entity_history::{EntityHistory, EntityHistoryBmc, HistoryOp};
+use super::label::{
+	select_task_ids_with_all_labels, select_task_ids_with_any_labels,
+};
+use super::modql_utils::time_to_sea_value;
+use super::page::{HasId, Page, PageOptions};
+use super::user::{User, UserBmc};
 use super::{Error, ModelManager, Result};
 use crate::ctx::Ctx;
+use crate::utils::{deserialize_time_opt, serialize_time_opt};
 
 use super::base::{self, DbBmc};
 
 #[derive(Clone, Debug, FromRow, Fields, Serialize)]
 pub struct Task {
-	id: i64,
-	title: String,
-	done: bool,
+	pub id: i64,
+	pub title: String,
+	pub done: bool,
+	pub description: Option<String>,
+	#[serde(serialize_with = "serialize_time_opt")]
+	pub due_at: Option<OffsetDateTime>,
+	pub priority: i32,
+	pub status: TaskStatus,
+	pub assignee_id: Option<i64>,
+	pub parent_id: Option<i64>,
+	#[serde(serialize_with = "serialize_time_opt")]
+	pub deleted_at: Option<OffsetDateTime>,
+	pub version: i64,
+}
+
+impl HasId for Task {
+	fn id(&self) -> i64 {
+		self.id
+	}
+}
+
+/// A task with its dependencies (ids) and its subtasks hierarchy.
+#[derive(Debug, Serialize)]
+pub struct TaskTree {
+	#[serde(flatten)]
+	pub task: Task,
+	pub depends_on: Vec<i64>,
+	pub subtasks: Vec<TaskTree>,
+}
+
+/// A task matching a `TaskBmc::search` query.
+#[derive(Debug, FromRow, Serialize)]
+pub struct TaskSearchHit {
+	#[serde(flatten)]
+	#[sqlx(flatten)]
+	pub task: Task,
+	/// The higher the better (title matches weigh more than description ones).
+	pub rank: f32,
+	/// Title and description excerpt, as html: the text html escaped
+	/// (e.g., `&lt;`), and the matches in `<b>...</b>`.
+	pub snippet: String,
+}
+
+/// Stored as the postgres enum `task_status`.
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	PartialEq,
+	Eq,
+	Serialize,
+	Deserialize,
+	sqlx::Type,
+	strum_macros::AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "task_status", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TaskStatus {
+	Todo,
+	InProgress,
+	Blocked,
+	Done,
+}
+
+impl TaskStatus {
+	/// The status of the `done` shortcut (`false` is back to todo).
+	fn from_done(done: bool) -> Self {
+		if done {
+			TaskStatus::Done
+		} else {
+			TaskStatus::Todo
+		}
+	}
 }
 
-#[derive(Deserialize, Fields)]
+impl From<TaskStatus> for sea_query::Value {
+	fn from(val: TaskStatus) -> Self {
+		val.as_ref().into()
+	}
+}
+
+impl sea_query::Nullable for TaskStatus {
+	fn null() -> sea_query::Value {
+		sea_query::Value::String(None)
+	}
+}
+
+#[derive(Deserialize, Fields, Default)]
 pub struct TaskForCreate {
 	pub title: String,
+	pub description: Option<String>,
+	#[serde(default, deserialize_with = "deserialize_time_opt")]
+	pub due_at: Option<OffsetDateTime>,
+	pub priority: Option<i32>,
+	#[field(cast_as = "task_status")]
+	pub status: Option<TaskStatus>,
 }
 
+/// Note: `done` is derived from `status` (in the db), and only a shortcut here
+///       for `status` (ignored if `status` is given).
 #[derive(Deserialize, Fields, Default)]
 pub struct TaskForUpdate {
 	title: Option<String>,
+	#[field(skip)]
 	done: Option<bool>,
+	description: Option<String>,
+	#[serde(default, deserialize_with = "deserialize_time_opt")]
+	due_at: Option<OffsetDateTime>,
+	priority: Option<i32>,
+	#[field(cast_as = "task_status")]
+	status: Option<TaskStatus>,
+	/// The optional fields to set back to null (wins over their value if any).
+	#[field(skip)]
+	#[serde(default)]
+	clear: Vec<TaskClearField>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskClearField {
+	Description,
+	DueAt,
+}
+
+/// The fields of a `TaskForUpdate` to update, with its `done` shortcut and
+/// `clear` applied (e.g., `description` to null).
+struct TaskUpdateFields(Fields);
+
+impl TaskUpdateFields {
+	/// Only the column `iden` set to `value` (e.g., a `None` to null).
+	fn one(iden: TaskIden, value: SimpleExpr) -> Self {
+		Self(Fields::new(vec![Field::new(iden, value)]))
+	}
+}
+
+impl From<TaskForUpdate> for TaskUpdateFields {
+	fn from(mut task_u: TaskForUpdate) -> Self {
+		if task_u.status.is_none() {
+			task_u.status = task_u.done.map(TaskStatus::from_done);
+		}
+
+		let clear = std::mem::take(&mut task_u.clear);
+		let mut clear_idens: Vec<TaskIden> = Vec::new();
+		for field in clear {
+			match field {
+				TaskClearField::Description => task_u.description = None,
+				TaskClearField::DueAt => task_u.due_at = None,
+			}
+			clear_idens.push(field.into());
+		}
+
+		let mut fields = task_u.not_none_fields();
+		for iden in clear_idens {
+			fields.push(Field::new(iden, SimpleExpr::Keyword(Keyword::Null)));
+		}
+
+		Self(fields)
+	}
+}
+
+impl HasFields for TaskUpdateFields {
+	fn not_none_fields(self) -> Fields {
+		self.0
+	}
+
+	fn all_fields(self) -> Fields {
+		self.0
+	}
+
+	fn field_names() -> &'static [&'static str] {
+		TaskForUpdate::field_names()
+	}
+
+	fn field_idens() -> Vec<DynIden> {
+		TaskForUpdate::field_idens()
+	}
+
+	fn field_column_refs() -> Vec<ColumnRef> {
+		TaskForUpdate::field_column_refs()
+	}
+
+	fn field_column_refs_with_rel(rel: impl IntoIden) -> Vec<ColumnRef> {
+		TaskForUpdate::field_column_refs_with_rel(rel)
+	}
 }
 
 #[derive(FilterNodes, Deserialize, Default, Debug)]
@@ -33,12 +222,151 @@ pub struct TaskFilter {
 	id: Option<OpValsInt64>,
 	title: Option<OpValsString>,
 	done: Option<OpValsBool>,
+	description: Option<OpValsString>,
+	#[modql(to_sea_value_fn = "time_to_sea_value")]
+	due_at: Option<OpValsValue>,
+	priority: Option<OpValsInt64>,
+	#[modql(cast_as = "task_status")]
+	status: Option<OpValsString>,
+	assignee_id: Option<OpValsInt64>,
+	parent_id: Option<OpValsInt64>,
+
+	/// Tasks watched (or not) by the ctx user.
+	/// Not a modql filter node, applied by `TaskFilter::into_condition`.
+	watched_by_me: Option<bool>,
+	/// Tasks having at least one of these label ids.
+	labels_any: Option<Vec<i64>>,
+	/// Tasks having all of these label ids.
+	labels_all: Option<Vec<i64>>,
+}
+
+impl TaskFilter {
+	/// Build the sea-query condition of this filter group, including the
+	/// properties that are not modql filter nodes
+	/// (e.g., `watched_by_me`, `labels_any`).
+	fn into_condition(mut self, ctx: &Ctx) -> Result<Condition> {
+		let watched_by_me = self.watched_by_me.take();
+		// (empty label lists are no condition, rather than an `IN ()`)
+		let labels_any = self.labels_any.take().filter(|ids| !ids.is_empty());
+		let labels_all = self.labels_all.take().filter(|ids| !ids.is_empty());
+
+		let mut condition = Condition::all().add(Condition::try_from(self)?);
+
+		if let Some(watched_by_me) = watched_by_me {
+			let watched_task_ids = Query::select()
+				.column(TaskWatcherIden::TaskId)
+				.from(TaskWatcherIden::Table)
+				.and_where(Expr::col(TaskWatcherIden::UserId).eq(ctx.user_id()))
+				.to_owned();
+			let id_col = Expr::col(TaskIden::Id);
+			condition = condition.add(if watched_by_me {
+				id_col.in_subquery(watched_task_ids)
+			} else {
+				id_col.not_in_subquery(watched_task_ids)
+			});
+		}
+
+		if let Some(labels_any) = labels_any {
+			condition = condition.add(
+				Expr::col(TaskIden::Id)
+					.in_subquery(select_task_ids_with_any_labels(&labels_any)),
+			);
+		}
+
+		if let Some(labels_all) = labels_all {
+			condition = condition.add(
+				Expr::col(TaskIden::Id)
+					.in_subquery(select_task_ids_with_all_labels(&labels_all)),
+			);
+		}
+
+		Ok(condition)
+	}
+}
+
+#[derive(Iden)]
+enum TaskIden {
+	Id,
+	Description,
+	DueAt,
+	AssigneeId,
+	ParentId,
+	Version,
+}
+
+impl From<TaskClearField> for TaskIden {
+	fn from(field: TaskClearField) -> Self {
+		match field {
+			TaskClearField::Description => TaskIden::Description,
+			TaskClearField::DueAt => TaskIden::DueAt,
+		}
+	}
+}
+
+#[derive(Iden)]
+enum TaskWatcherIden {
+	#[iden = "task_watcher"]
+	Table,
+	TaskId,
+	UserId,
+}
+
+#[derive(Iden)]
+enum TaskDependencyIden {
+	#[iden = "task_dependency"]
+	Table,
+	TaskId,
+	DependsOnId,
 }
 
+/// Is `$2` in the parent chain of `$1` (`$1` included)?
+const SQL_IS_ANCESTOR: &str = r#"
+WITH RECURSIVE ancestors(id, parent_id) AS (
+  SELECT id, parent_id FROM "task" WHERE id = $1
+  UNION
+  SELECT t.id, t.parent_id FROM "task" t JOIN ancestors a ON t.id = a.parent_id
+)
+SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2)"#;
+
+/// Does `$1` (transitively) depend on `$2`?
+const SQL_DEPENDS_ON: &str = r#"
+WITH RECURSIVE deps(id) AS (
+  SELECT depends_on_id FROM "task_dependency" WHERE task_id = $1
+  UNION
+  SELECT d.depends_on_id FROM "task_dependency" d JOIN deps ON d.task_id = deps.id
+)
+SELECT EXISTS (SELECT 1 FROM deps WHERE id = $2)"#;
+
+// -- Full-text search (`$1` is the websearch query, e.g., `"foo bar" -baz`)
+const SQL_SEARCH_MATCH: &str = "search @@ websearch_to_tsquery('english', $1)";
+const SQL_SEARCH_RANK: &str = "ts_rank(search, websearch_to_tsquery('english', $1))";
+// (the text is html escaped before the `<b>` markers are added)
+const SQL_SEARCH_SNIPPET: &str = "ts_headline('english', \
+	replace(replace(replace(replace(\
+		title || ' ' || coalesce(description, ''), \
+		'&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '\"', '&quot;'), \
+	websearch_to_tsquery('english', $1), \
+	'StartSel=<b>, StopSel=</b>, MaxFragments=2')";
+
+/// Transaction-scoped advisory lock (released at the commit/rollback).
+const SQL_ADVISORY_XACT_LOCK: &str = "SELECT pg_advisory_xact_lock(hashtext($1))";
+const LOCK_KEY_TASK_PARENT: &str = "task.parent_id";
+const LOCK_KEY_TASK_DEPENDENCY: &str = "task_dependency";
+
+/// Ids of `$1` and all of its subtasks (recursively).
+const SQL_TREE_IDS: &str = r#"
+WITH RECURSIVE tree(id) AS (
+  SELECT id FROM "task" WHERE id = $1
+  UNION
+  SELECT t.id FROM "task" t JOIN tree ON t.parent_id = tree.id
+)
+SELECT id FROM tree"#;
+
 pub struct TaskBmc;
 
 impl DbBmc for TaskBmc {
 	const TABLE: &'static str = "task";
+	const SOFT_DELETE: bool = true;
 }
 
 impl TaskBmc {
@@ -54,27 +382,608 @@ impl TaskBmc {
 		ctx: &Ctx,
 		mm: &ModelManager,
 		id: i64,
+		version: Option<i64>,
 		payload: TaskForUpdate,
 	) -> Result<Task> {
-		base::update::<Self, _, TaskForUpdate>(ctx, mm, id, payload).await
+		let fields = TaskUpdateFields::from(payload);
+		base::update::<Self, _, _>(ctx, mm, id, version, fields).await
 	}
 
 	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
 		base::get::<Self, _>(ctx, mm, id).await
 	}
 
+	/// The changes of the task `id` (see `EntityHistoryBmc`), oldest first.
+	pub async fn history(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<EntityHistory>> {
+		EntityHistoryBmc::list_page_for_entity(
+			ctx,
+			mm,
+			Self::TABLE,
+			id,
+			list_options,
+			page_options,
+		)
+		.await
+	}
+
+	/// Records the link (or unlink) of `linked_id` (e.g., a dependency) in the
+	/// history of the task `id`, as `{key: linked_id}`.
+	async fn record_link(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		op: HistoryOp,
+		key: &str,
+		linked_id: i64,
+	) -> Result<()> {
+		let changes = json!({ key: linked_id });
+		EntityHistoryBmc::record(ctx, mm, Self::TABLE, id, op, Some(changes)).await
+	}
+
 	pub async fn list(
 		ctx: &Ctx,
 		mm: &ModelManager,
 		filters: Option<Vec<TaskFilter>>,
 		list_options: Option<ListOptions>,
 	) -> Result<Vec<Task>> {
-		base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+		let condition = Self::filters_condition(ctx, filters)?;
+		base::list_by_condition::<Self, _>(ctx, mm, condition, list_options).await
+	}
+
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<TaskFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<Task>> {
+		let condition = Self::filters_condition(ctx, filters)?;
+		base::list_page_by_condition::<Self, _>(
+			ctx,
+			mm,
+			condition,
+			list_options,
+			page_options,
+		)
+		.await
+	}
+
+	/// Full-text search over the title and description, best ranked first
+	/// (then by id), narrowed by the `filters`.
+	///
+	/// The page is offset based, as a custom `order_bys` list page (see
+	/// `base::list_page_by_condition`): no cursors, and a `cursor` is an
+	/// `Error::InvalidCursor`.
+	///
+	/// Note: The `list_options.order_bys` are ignored (ordered by rank).
+	pub async fn search(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		search: &str,
+		filters: Option<Vec<TaskFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<TaskSearchHit>> {
+		let dbx = mm.dbx();
+		if let Some(cursor) = page_options.cursor {
+			return Err(Error::InvalidCursor { cursor });
+		}
+		let condition = Self::filters_condition(ctx, filters)?;
+		let condition = Condition::all()
+			.add_option(base::scoped_condition::<Self>(condition, false))
+			.add(Expr::cust_with_values(SQL_SEARCH_MATCH, [search]));
+
+		// -- Total
+		let total = if page_options.with_total {
+			let mut query = Query::select();
+			query
+				.from(Self::table_ref())
+				.expr(Expr::col(Asterisk).count())
+				.cond_where(condition.clone());
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+			let (total,) = dbx.fetch_one(sqlx_query).await?;
+			Some(total)
+		} else {
+			None
+		};
+
+		// build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Task::field_column_refs())
+			.expr_as(
+				Expr::cust_with_values(SQL_SEARCH_RANK, [search]),
+				Alias::new("rank"),
+			)
+			.expr_as(
+				Expr::cust_with_values(SQL_SEARCH_SNIPPET, [search]),
+				Alias::new("snippet"),
+			)
+			.cond_where(condition)
+			.order_by(Alias::new("rank"), Order::Desc)
+			.order_by(TaskIden::Id, Order::Asc);
+
+		let mut list_options = base::finalize_list_options(list_options)?;
+		list_options.order_bys = None;
+		list_options.apply_to_sea_query(&mut query);
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, TaskSearchHit, _>(&sql, values);
+		let hits = dbx.fetch_all(sqlx_query).await?;
+
+		Ok(Page {
+			items: hits,
+			next_cursor: None,
+			prev_cursor: None,
+			total,
+		})
+	}
+
+	/// Filters are OR between them (see modql FilterGroups).
+	fn filters_condition(
+		ctx: &Ctx,
+		filters: Option<Vec<TaskFilter>>,
+	) -> Result<Option<Condition>> {
+		let Some(filters) = filters else {
+			return Ok(None);
+		};
+
+		let mut condition = Condition::any();
+		for filter in filters {
+			condition = condition.add(filter.into_condition(ctx)?);
+		}
+
+		Ok(Some(condition))
 	}
 
 	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
 		base::delete::<Self>(ctx, mm, id).await
 	}
+
+	// region:    --- Trash
+
+	pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
+		base::restore::<Self>(ctx, mm, id).await?;
+		Self::get(ctx, mm, id).await
+	}
+
+	pub async fn list_deleted_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<TaskFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<Task>> {
+		let condition = Self::filters_condition(ctx, filters)?;
+		base::list_deleted_page_by_condition::<Self, _>(
+			ctx,
+			mm,
+			condition,
+			list_options,
+			page_options,
+		)
+		.await
+	}
+
+	/// Permanently deletes the tasks deleted for longer than `older_than`
+	/// (admin only). Returns the number of purged tasks.
+	pub async fn purge_deleted(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		older_than: Duration,
+	) -> Result<u64> {
+		UserBmc::ensure_admin(ctx)?;
+		base::purge_deleted::<Self>(ctx, mm, older_than).await
+	}
+
+	// endregion: --- Trash
+
+	/// All or nothing (see `base::create_many`).
+	pub async fn create_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		payloads: Vec<TaskForCreate>,
+	) -> Result<Vec<Task>> {
+		base::create_many::<Self, _, _>(ctx, mm, payloads).await
+	}
+
+	/// All or nothing (see `base::update_many`).
+	pub async fn update_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		items: Vec<(i64, Option<i64>, TaskForUpdate)>,
+	) -> Result<Vec<Task>> {
+		let items = items
+			.into_iter()
+			.map(|(id, version, payload)| {
+				(id, version, TaskUpdateFields::from(payload))
+			})
+			.collect();
+		base::update_many::<Self, _, _>(ctx, mm, items).await
+	}
+
+	/// All or nothing (see `base::delete_many`).
+	pub async fn delete_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		ids: Vec<i64>,
+	) -> Result<()> {
+		base::delete_many::<Self>(ctx, mm, ids).await
+	}
+
+	/// Assign the task to `assignee_id` (must be an existing user),
+	/// or unassign it with `None`.
+	///
+	/// Note: An update (see `base::update` for the `version`).
+	pub async fn assign(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+		assignee_id: Option<i64>,
+	) -> Result<Task> {
+		if let Some(assignee_id) = assignee_id {
+			let _: User = UserBmc::get(ctx, mm, assignee_id).await?;
+		}
+
+		let fields = TaskUpdateFields::one(TaskIden::AssigneeId, assignee_id.into());
+		base::update::<Self, _, _>(ctx, mm, id, version, fields).await
+	}
+
+	/// Note: The link and its `entity_history` row are in one transaction
+	///       (the same for the other links, e.g., dependencies).
+	pub async fn add_watcher(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		user_id: i64,
+	) -> Result<()> {
+		let mm = mm.new_with_txn();
+		mm.begin_txn().await?;
+
+		let res = async {
+			let dbx = mm.dbx();
+
+			Self::get(ctx, &mm, id).await?;
+			let _: User = UserBmc::get(ctx, &mm, user_id).await?;
+
+			// build query
+			let mut query = Query::insert();
+			query
+				.into_table(TaskWatcherIden::Table)
+				.columns([TaskWatcherIden::TaskId, TaskWatcherIden::UserId])
+				.values([id.into(), user_id.into()])?
+				.on_conflict(
+					OnConflict::columns([
+						TaskWatcherIden::TaskId,
+						TaskWatcherIden::UserId,
+					])
+					.do_nothing()
+					.to_owned(),
+				);
+
+			// exec query
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = dbx.execute(sqlx_query).await?;
+
+			// (no change if already watching)
+			if count == 1 {
+				let op = HistoryOp::Link;
+				Self::record_link(ctx, &mm, id, op, "watcher_id", user_id).await?;
+			}
+
+			Ok(())
+		}
+		.await;
+
+		mm.end_txn(res).await
+	}
+
+	pub async fn remove_watcher(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		user_id: i64,
+	) -> Result<()> {
+		let mm = mm.new_with_txn();
+		mm.begin_txn().await?;
+
+		let res = async {
+			let dbx = mm.dbx();
+
+			// build query
+			let mut query = Query::delete();
+			query
+				.from_table(TaskWatcherIden::Table)
+				.and_where(Expr::col(TaskWatcherIden::TaskId).eq(id))
+				.and_where(Expr::col(TaskWatcherIden::UserId).eq(user_id));
+
+			// exec query
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = dbx.execute(sqlx_query).await?;
+
+			if count == 1 {
+				let op = HistoryOp::Unlink;
+				Self::record_link(ctx, &mm, id, op, "watcher_id", user_id).await?;
+			}
+
+			Ok(())
+		}
+		.await;
+
+		mm.end_txn(res).await
+	}
+
+	// region:    --- Subtasks
+
+	/// Make the task `id` a subtask of `parent_id`, or a root task with `None`.
+	/// Fails with `Error::CycleDetected` if `parent_id` is `id` or one of its
+	/// subtasks.
+	pub async fn set_parent(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		parent_id: Option<i64>,
+	) -> Result<Task> {
+		// The cycle check and the update are in one transaction, serialized
+		// with the other parent changes (see `lock_graph`).
+		let mm = mm.new_with_txn();
+		mm.begin_txn().await?;
+
+		let res = async {
+			let dbx = mm.dbx();
+			lock_graph(&mm, LOCK_KEY_TASK_PARENT).await?;
+
+			Self::get(ctx, &mm, id).await?;
+			if let Some(parent_id) = parent_id {
+				Self::get(ctx, &mm, parent_id).await?;
+				let sqlx_query = sqlx::query_as::<_, (bool,)>(SQL_IS_ANCESTOR)
+					.bind(parent_id)
+					.bind(id);
+				let (is_cycle,) = dbx.fetch_one(sqlx_query).await?;
+				if is_cycle {
+					return Err(Error::CycleDetected {
+						entity: Self::TABLE,
+						id,
+						target_id: parent_id,
+					});
+				}
+			}
+
+			// (an update, with its version and history, in this transaction)
+			let fields = TaskUpdateFields::one(TaskIden::ParentId, parent_id.into());
+			base::update::<Self, _, _>(ctx, &mm, id, None, fields).await
+		}
+		.await;
+
+		mm.end_txn(res).await
+	}
+
+	/// The task `id` with all of its subtasks (recursively) and their
+	/// dependencies.
+	pub async fn get_tree(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<TaskTree> {
+		let dbx = mm.dbx();
+
+		let root = Self::get(ctx, mm, id).await?;
+		let sqlx_query = sqlx::query_as::<_, (i64,)>(SQL_TREE_IDS).bind(id);
+		let ids: Vec<i64> = dbx
+			.fetch_all(sqlx_query)
+			.await?
+			.into_iter()
+			.map(|(id,)| id)
+			.collect();
+
+		// -- Get the subtasks
+		// (the deleted ones too, their live subtasks are lifted in build_tree)
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Task::field_column_refs())
+			.and_where(Expr::col(TaskIden::Id).is_in(ids.clone()))
+			.and_where(Expr::col(TaskIden::Id).ne(id))
+			.order_by(TaskIden::Id, Order::Asc);
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, Task, _>(&sql, values);
+		let subtasks = dbx.fetch_all(sqlx_query).await?;
+
+		// -- Get the dependencies
+		let mut query = Query::select();
+		query
+			.columns([TaskDependencyIden::TaskId, TaskDependencyIden::DependsOnId])
+			.from(TaskDependencyIden::Table)
+			.and_where(Expr::col(TaskDependencyIden::TaskId).is_in(ids))
+			.order_by(TaskDependencyIden::DependsOnId, Order::Asc);
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (i64, i64), _>(&sql, values);
+		let dependencies = dbx.fetch_all(sqlx_query).await?;
+
+		// -- Build the tree
+		let mut children: HashMap<i64, Vec<Task>> = HashMap::new();
+		for task in subtasks {
+			if let Some(parent_id) = task.parent_id {
+				children.entry(parent_id).or_default().push(task);
+			}
+		}
+		let mut depends_on: HashMap<i64, Vec<i64>> = HashMap::new();
+		for (task_id, depends_on_id) in dependencies {
+			depends_on.entry(task_id).or_default().push(depends_on_id);
+		}
+
+		Ok(build_tree(root, &mut children, &mut depends_on))
+	}
+
+	// endregion: --- Subtasks
+
+	// region:    --- Dependencies
+
+	/// Make the task `id` depend on (be blocked by) `depends_on_id`.
+	/// Fails with `Error::CycleDetected` if `depends_on_id` already depends,
+	/// directly or not, on `id`.
+	pub async fn add_dependency(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		depends_on_id: i64,
+	) -> Result<()> {
+		// The cycle check and the insert are in one transaction, serialized
+		// with the other dependency changes (see `lock_graph`).
+		let mm = mm.new_with_txn();
+		mm.begin_txn().await?;
+
+		let res = async {
+			let dbx = mm.dbx();
+			lock_graph(&mm, LOCK_KEY_TASK_DEPENDENCY).await?;
+
+			Self::get(ctx, &mm, id).await?;
+			Self::get(ctx, &mm, depends_on_id).await?;
+
+			let is_cycle = if id == depends_on_id {
+				true
+			} else {
+				let sqlx_query = sqlx::query_as::<_, (bool,)>(SQL_DEPENDS_ON)
+					.bind(depends_on_id)
+					.bind(id);
+				dbx.fetch_one(sqlx_query).await?.0
+			};
+			if is_cycle {
+				return Err(Error::CycleDetected {
+					entity: Self::TABLE,
+					id,
+					target_id: depends_on_id,
+				});
+			}
+
+			// build query
+			let mut query = Query::insert();
+			query
+				.into_table(TaskDependencyIden::Table)
+				.columns([
+					TaskDependencyIden::TaskId,
+					TaskDependencyIden::DependsOnId,
+				])
+				.values([id.into(), depends_on_id.into()])?
+				.on_conflict(
+					OnConflict::columns([
+						TaskDependencyIden::TaskId,
+						TaskDependencyIden::DependsOnId,
+					])
+					.do_nothing()
+					.to_owned(),
+				);
+
+			// exec query
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = dbx.execute(sqlx_query).await?;
+
+			// (no change if already a dependency)
+			if count == 1 {
+				let op = HistoryOp::Link;
+				Self::record_link(ctx, &mm, id, op, "depends_on_id", depends_on_id)
+					.await?;
+			}
+
+			Ok(())
+		}
+		.await;
+
+		mm.end_txn(res).await
+	}
+
+	pub async fn remove_dependency(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		depends_on_id: i64,
+	) -> Result<()> {
+		let mm = mm.new_with_txn();
+		mm.begin_txn().await?;
+
+		let res = async {
+			let dbx = mm.dbx();
+
+			// build query
+			let mut query = Query::delete();
+			query
+				.from_table(TaskDependencyIden::Table)
+				.and_where(Expr::col(TaskDependencyIden::TaskId).eq(id))
+				.and_where(
+					Expr::col(TaskDependencyIden::DependsOnId).eq(depends_on_id),
+				);
+
+			// exec query
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = dbx.execute(sqlx_query).await?;
+
+			if count == 1 {
+				let op = HistoryOp::Unlink;
+				Self::record_link(ctx, &mm, id, op, "depends_on_id", depends_on_id)
+					.await?;
+			}
+
+			Ok(())
+		}
+		.await;
+
+		mm.end_txn(res).await
+	}
+
+	// endregion: --- Dependencies
+}
+
+fn build_tree(
+	task: Task,
+	children: &mut HashMap<i64, Vec<Task>>,
+	depends_on: &mut HashMap<i64, Vec<i64>>,
+) -> TaskTree {
+	TaskTree {
+		depends_on: depends_on.remove(&task.id).unwrap_or_default(),
+		subtasks: build_subtasks(task.id, children, depends_on),
+		task,
+	}
+}
+
+/// The soft deleted subtasks are not part of the tree, but their live
+/// subtasks are, in place of them.
+fn build_subtasks(
+	parent_id: i64,
+	children: &mut HashMap<i64, Vec<Task>>,
+	depends_on: &mut HashMap<i64, Vec<i64>>,
+) -> Vec<TaskTree> {
+	let mut subtasks = Vec::new();
+	for task in children.remove(&parent_id).unwrap_or_default() {
+		if task.deleted_at.is_some() {
+			subtasks.extend(build_subtasks(task.id, children, depends_on));
+		} else {
+			subtasks.push(build_tree(task, children, depends_on));
+		}
+	}
+	subtasks
+}
+
+/// Serializes the changes of a task graph (parents or dependencies) until the
+/// end of the current transaction, so that two concurrent changes cannot
+/// each pass the cycle check and create a cycle together.
+async fn lock_graph(mm: &ModelManager, key: &str) -> Result<()> {
+	let sqlx_query = sqlx::query(SQL_ADVISORY_XACT_LOCK).bind(key);
+	mm.dbx().execute(sqlx_query).await?;
+	Ok(())
 }
 
 #[cfg(test)]
@@ -82,7 +991,9 @@ mod tests {
 	use super::*;
 	use crate::_dev_utils;
 	use anyhow::Result;
+	use crate::utils::{format_time, now_utc};
 	use axum::routing::delete;
+	use serde_json::json;
 	use serial_test::serial;
 
 	#[serial]
@@ -98,6 +1009,7 @@ mod tests {
 			&mm,
 			TaskForCreate {
 				title: fx_title.to_string(),
+				..Default::default()
 			},
 		)
 		.await?;
@@ -125,6 +1037,7 @@ mod tests {
 			&mm,
 			TaskForCreate {
 				title: fx_title.to_string(),
+				..Default::default()
 			},
 		)
 		.await?;
@@ -138,6 +1051,7 @@ mod tests {
 			&ctx,
 			&mm,
 			id,
+			Some(task.version),
 			TaskForUpdate {
 				title: Some("test_task_update_title".to_string()),
 				..Default::default()
@@ -145,6 +1059,28 @@ mod tests {
 		)
 		.await?;
 		assert_eq!("test_task_update_title", task.title);
+		assert_eq!(task.version, 2, "version incremented");
+
+		// Update at a stale version
+		let res = TaskBmc::update(
+			&ctx,
+			&mm,
+			id,
+			Some(1),
+			TaskForUpdate {
+				title: Some("test_task_update_stale".to_string()),
+				..Default::default()
+			},
+		)
+		.await;
+		match res {
+			Err(Error::VersionConflict { expected, current, .. }) => {
+				assert_eq!(expected, 1);
+				assert_eq!(current["version"], 2);
+				assert_eq!(current["title"], "test_task_update_title");
+			}
+			other => panic!("should be VersionConflict {other:?}"),
+		}
 
 		// Cleanup
 		TaskBmc::delete(&ctx, &mm, id).await?;
@@ -154,10 +1090,64 @@ mod tests {
 
 	#[serial]
 	#[tokio::test]
-	async fn test_task_get_not_found() -> Result<()> {
+	async fn test_task_update_done_status_and_clear() -> Result<()> {
 		let mm = _dev_utils::init_test().await;
 		let ctx = Ctx::root_ctx();
-		let fx_id = 100;
+		let task = TaskBmc::create(
+			&ctx,
+			&mm,
+			TaskForCreate {
+				title: "test_task_update_done_status".to_string(),
+				description: Some("some description".to_string()),
+				due_at: Some(now_utc()),
+				status: Some(TaskStatus::Done),
+				..Default::default()
+			},
+		)
+		.await?;
+		let id = task.id;
+		assert!(task.done, "done from status on create");
+
+		// -- done shortcut
+		let task_u = TaskForUpdate {
+			done: Some(false),
+			..Default::default()
+		};
+		let task = TaskBmc::update(&ctx, &mm, id, Some(task.version), task_u).await?;
+		assert_eq!(task.status, TaskStatus::Todo);
+		assert!(!task.done);
+
+		// -- status wins over done
+		let task_u = TaskForUpdate {
+			done: Some(false),
+			status: Some(TaskStatus::Done),
+			..Default::default()
+		};
+		let task = TaskBmc::update(&ctx, &mm, id, Some(task.version), task_u).await?;
+		assert_eq!(task.status, TaskStatus::Done);
+		assert!(task.done);
+
+		// -- clear
+		let task_u: TaskForUpdate = serde_json::from_value(json!({
+			"description": "ignored",
+			"clear": ["description", "due_at"]
+		}))?;
+		let task = TaskBmc::update(&ctx, &mm, id, Some(task.version), task_u).await?;
+		assert_eq!(task.description, None);
+		assert_eq!(task.due_at, None);
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_get_not_found() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = 100;
 
 		// Execute
 		let task = TaskBmc::get(&ctx, &mm, fx_id).await;
@@ -215,4 +1205,726 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_search() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks = [
+			("test_search deploy pipeline", Some("fix the flaky step"), 1),
+			("test_search write docs", Some("about the deploy pipeline"), 0),
+			("test_search unrelated", None, 1),
+			("test_search <i>escaped</i> & \"quoted\"", None, 1),
+		];
+		let mut tasks = Vec::new();
+		for (title, description, priority) in fx_tasks {
+			let task = TaskBmc::create(
+				&ctx,
+				&mm,
+				TaskForCreate {
+					title: title.to_string(),
+					description: description.map(|d| d.to_string()),
+					priority: Some(priority),
+					..Default::default()
+				},
+			)
+			.await?;
+			tasks.push(task);
+		}
+
+		// Check ranked (title match first)
+		let page_options: PageOptions =
+			serde_json::from_value(json!({"with_total": true}))?;
+		let page =
+			TaskBmc::search(&ctx, &mm, "deploy pipeline", None, None, page_options)
+				.await?;
+		assert_eq!(page.total, Some(2), "total hits");
+		let hits = page.items;
+		let ids: Vec<i64> = hits.iter().map(|h| h.task.id).collect();
+		assert_eq!(ids, [tasks[0].id, tasks[1].id]);
+		assert!(hits[0].rank > hits[1].rank, "title match ranks higher");
+		assert!(hits[0].snippet.contains("<b>deploy</b>"), "snippet");
+
+		// Check snippet html escaped
+		let hits = TaskBmc::search(
+			&ctx,
+			&mm,
+			"escaped",
+			None,
+			None,
+			PageOptions::default(),
+		)
+		.await?
+		.items;
+		assert_eq!(hits.len(), 1, "escaped hits");
+		let snippet = &hits[0].snippet;
+		assert!(
+			snippet.starts_with(
+				"test_search &lt;i&gt;<b>escaped</b>&lt;/i&gt; &amp; &quot;quoted"
+			),
+			"snippet escaped {snippet}"
+		);
+		assert!(!snippet.contains("<i>") && !snippet.contains('"'), "no raw html");
+
+		// Check with filters
+		let filter: TaskFilter =
+			serde_json::from_value(json!({"priority": {"$eq": 0}}))?;
+		let hits = TaskBmc::search(
+			&ctx,
+			&mm,
+			"deploy",
+			Some(vec![filter]),
+			None,
+			PageOptions::default(),
+		)
+		.await?
+		.items;
+		let ids: Vec<i64> = hits.iter().map(|h| h.task.id).collect();
+		assert_eq!(ids, [tasks[1].id]);
+
+		// Check offset paging
+		let list_options: ListOptions =
+			serde_json::from_value(json!({"limit": 1, "offset": 1}))?;
+		let page = TaskBmc::search(
+			&ctx,
+			&mm,
+			"deploy pipeline",
+			None,
+			Some(list_options),
+			PageOptions::default(),
+		)
+		.await?;
+		let ids: Vec<i64> = page.items.iter().map(|h| h.task.id).collect();
+		assert_eq!(ids, [tasks[1].id], "second hit page");
+		assert!(page.next_cursor.is_none(), "offset based, no cursor");
+
+		// Cleanup
+		for task in tasks {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_list_by_due_at_priority_status() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let now = now_utc();
+		let fx_tasks = [
+			("test_task_range_1", 1, 1, TaskStatus::Todo),
+			("test_task_range_2", 5, 2, TaskStatus::InProgress),
+			("test_task_range_3", 9, 3, TaskStatus::Blocked),
+		];
+		for (title, priority, due_days, status) in fx_tasks {
+			TaskBmc::create(
+				&ctx,
+				&mm,
+				TaskForCreate {
+					title: title.to_string(),
+					priority: Some(priority),
+					due_at: Some(now + time::Duration::days(due_days)),
+					status: Some(status),
+					..Default::default()
+				},
+			)
+			.await?;
+		}
+
+		// Execute
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_task_range_"},
+			"priority": {"$gte": 5},
+			"due_at": {"$lt": format_time(now + time::Duration::hours(60))},
+		}))?;
+		let tasks = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+
+		// Check
+		assert_eq!(tasks.len(), 1, "number of matching tasks");
+		assert_eq!(tasks[0].title, "test_task_range_2");
+		assert_eq!(tasks[0].status, TaskStatus::InProgress);
+
+		// Execute status filter
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_task_range_"},
+			"status": {"$in": ["blocked", "done"]},
+		}))?;
+		let tasks = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+		assert_eq!(tasks.len(), 1, "number of blocked/done tasks");
+		assert_eq!(tasks[0].title, "test_task_range_3");
+
+		// Cleanup
+		let filter: TaskFilter = serde_json::from_value(json!({
+			"title": {"$startsWith": "test_task_range_"},
+		}))?;
+		for task in TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await? {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_assign() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_user_id = 1000;
+		let tasks = _dev_utils::seed_tasks(&ctx, &mm, &["test_task_assign"]).await?;
+		let id = tasks[0].id;
+
+		// Assign
+		let task =
+			TaskBmc::assign(&ctx, &mm, id, Some(tasks[0].version), Some(fx_user_id))
+				.await?;
+		assert_eq!(task.assignee_id, Some(fx_user_id));
+		assert_eq!(task.version, tasks[0].version + 1, "version incremented");
+
+		// Assign at a stale version
+		let res =
+			TaskBmc::assign(&ctx, &mm, id, Some(tasks[0].version), None).await;
+		assert!(
+			matches!(res, Err(Error::VersionConflict { entity: "task", .. })),
+			"stale version {res:?}"
+		);
+
+		// Filter by assignee
+		let filter: TaskFilter =
+			serde_json::from_value(json!({ "assignee_id": fx_user_id }))?;
+		let tasks = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+		assert!(tasks.iter().any(|t| t.id == id), "assigned task listed");
+
+		// Assign to unknown user
+		let res = TaskBmc::assign(&ctx, &mm, id, None, Some(9999)).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { entity: "user", id: 9999 })),
+			"unknown assignee {res:?}"
+		);
+
+		// Unassign
+		let task = TaskBmc::assign(&ctx, &mm, id, Some(task.version), None).await?;
+		assert_eq!(task.assignee_id, None);
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_history_of_assign_and_links() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		let tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&["test_task_history_1", "test_task_history_2"],
+		)
+		.await?;
+		let (id, other_id) = (tasks[0].id, tasks[1].id);
+
+		// Execute
+		TaskBmc::assign(&ctx, &mm, id, None, Some(1000)).await?;
+		TaskBmc::set_parent(&ctx, &mm, id, Some(other_id)).await?;
+		TaskBmc::add_dependency(&ctx, &mm, id, other_id).await?;
+		TaskBmc::add_watcher(&ctx, &mm, id, 1000).await?;
+		// (already watching, no change)
+		TaskBmc::add_watcher(&ctx, &mm, id, 1000).await?;
+		TaskBmc::remove_watcher(&ctx, &mm, id, 1000).await?;
+
+		// Check
+		let history =
+			TaskBmc::history(&ctx, &mm, id, None, PageOptions::default()).await?;
+		let entries: Vec<(&str, Option<&serde_json::Value>)> = history
+			.items
+			.iter()
+			.map(|h| (h.op.as_str(), h.changes.as_ref()))
+			.collect();
+		assert_eq!(
+			entries[1..],
+			[
+				("update", Some(&json!({"assignee_id": 1000}))),
+				("update", Some(&json!({"parent_id": other_id}))),
+				("link", Some(&json!({"depends_on_id": other_id}))),
+				("link", Some(&json!({"watcher_id": 1000}))),
+				("unlink", Some(&json!({"watcher_id": 1000}))),
+			]
+		);
+		assert!(
+			history.items.iter().all(|h| h.user_id == 1000),
+			"user of changes"
+		);
+
+		// Check paging
+		let page_options: PageOptions =
+			serde_json::from_value(json!({"with_total": true}))?;
+		let list_options: ListOptions = serde_json::from_value(json!({"limit": 2}))?;
+		let page =
+			TaskBmc::history(&ctx, &mm, id, Some(list_options), page_options).await?;
+		assert_eq!(page.items.len(), 2, "history page size");
+		assert_eq!(page.total, Some(6), "history total");
+		assert!(page.next_cursor.is_some(), "history next page");
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		TaskBmc::delete(&ctx, &mm, other_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_watch_unwatch() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?;
+		let tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&["test_task_watch_1", "test_task_watch_2"],
+		)
+		.await?;
+		let watched_filter = || -> Result<Vec<TaskFilter>> {
+			Ok(vec![serde_json::from_value(json!({
+				"title": {"$startsWith": "test_task_watch_"},
+				"watched_by_me": true,
+			}))?])
+		};
+
+		// Watch
+		TaskBmc::add_watcher(&ctx, &mm, tasks[0].id, ctx.user_id()).await?;
+		let watched =
+			TaskBmc::list(&ctx, &mm, Some(watched_filter()?), None).await?;
+		assert_eq!(watched.len(), 1, "number of watched tasks");
+		assert_eq!(watched[0].id, tasks[0].id);
+
+		// Unwatch
+		TaskBmc::remove_watcher(&ctx, &mm, tasks[0].id, ctx.user_id()).await?;
+		let watched =
+			TaskBmc::list(&ctx, &mm, Some(watched_filter()?), None).await?;
+		assert!(watched.is_empty(), "no more watched tasks");
+
+		// Cleanup
+		for task in tasks {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_subtasks_tree_and_cycle() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&[
+				"test_task_tree_root",
+				"test_task_tree_child",
+				"test_task_tree_leaf",
+			],
+		)
+		.await?;
+		let (root, child, leaf) = (tasks[0].id, tasks[1].id, tasks[2].id);
+
+		// root > child > leaf
+		TaskBmc::set_parent(&ctx, &mm, child, Some(root)).await?;
+		let task = TaskBmc::set_parent(&ctx, &mm, leaf, Some(child)).await?;
+		assert_eq!(task.parent_id, Some(child));
+
+		// Check tree
+		let tree = TaskBmc::get_tree(&ctx, &mm, root).await?;
+		assert_eq!(tree.subtasks.len(), 1, "root subtasks");
+		assert_eq!(tree.subtasks[0].task.id, child);
+		assert_eq!(tree.subtasks[0].subtasks[0].task.id, leaf);
+
+		// Soft deleted subtask (its subtasks take its place)
+		TaskBmc::delete(&ctx, &mm, child).await?;
+		let tree = TaskBmc::get_tree(&ctx, &mm, root).await?;
+		assert_eq!(tree.subtasks.len(), 1, "root subtasks");
+		assert_eq!(tree.subtasks[0].task.id, leaf);
+		TaskBmc::restore(&ctx, &mm, child).await?;
+
+		// Check cycles (self and descendant as parent)
+		for parent_id in [root, leaf] {
+			let res = TaskBmc::set_parent(&ctx, &mm, root, Some(parent_id)).await;
+			assert!(
+				matches!(res, Err(Error::CycleDetected { id, .. }) if id == root),
+				"parent cycle {res:?}"
+			);
+		}
+
+		// Unlink
+		let task = TaskBmc::set_parent(&ctx, &mm, child, None).await?;
+		assert_eq!(task.parent_id, None);
+		let tree = TaskBmc::get_tree(&ctx, &mm, root).await?;
+		assert!(tree.subtasks.is_empty(), "no more root subtasks");
+
+		// Cleanup
+		for task in tasks {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_dependencies_and_cycle() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&["test_task_dep_a", "test_task_dep_b", "test_task_dep_c"],
+		)
+		.await?;
+		let (a, b, c) = (tasks[0].id, tasks[1].id, tasks[2].id);
+
+		// a -> b -> c
+		TaskBmc::add_dependency(&ctx, &mm, a, b).await?;
+		TaskBmc::add_dependency(&ctx, &mm, b, c).await?;
+		let tree = TaskBmc::get_tree(&ctx, &mm, a).await?;
+		assert_eq!(tree.depends_on, vec![b]);
+
+		// Check cycles (c -> a, a -> a)
+		for (id, depends_on_id) in [(c, a), (a, a)] {
+			let res = TaskBmc::add_dependency(&ctx, &mm, id, depends_on_id).await;
+			assert!(
+				matches!(res, Err(Error::CycleDetected { .. })),
+				"dependency cycle {res:?}"
+			);
+		}
+
+		// Once b -> c removed, c -> a is fine
+		TaskBmc::remove_dependency(&ctx, &mm, b, c).await?;
+		TaskBmc::add_dependency(&ctx, &mm, c, a).await?;
+
+		// Cleanup
+		for task in tasks {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_list_page_cursors_and_total() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let titles = ["page_1", "page_2", "page_3", "page_4", "page_5"]
+			.map(|t| format!("test_task_list_page_{t}"));
+		let titles: Vec<&str> = titles.iter().map(|t| t.as_str()).collect();
+		let tasks = _dev_utils::seed_tasks(&ctx, &mm, &titles).await?;
+		let ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+		let filters = || -> Result<Option<Vec<TaskFilter>>> {
+			Ok(Some(vec![serde_json::from_value(json!({
+				"title": {"$startsWith": "test_task_list_page_"}
+			}))?]))
+		};
+		let list_options = || {
+			Some(ListOptions {
+				limit: Some(2),
+				..Default::default()
+			})
+		};
+		let page_ids = |page: &Page<Task>| -> Vec<i64> {
+			page.items.iter().map(|t| t.id).collect()
+		};
+
+		// First page (with total)
+		let page = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			list_options(),
+			PageOptions {
+				cursor: None,
+				with_total: true,
+			},
+		)
+		.await?;
+		assert_eq!(page_ids(&page), ids[0..2]);
+		assert_eq!(page.total, Some(5));
+		assert!(page.prev_cursor.is_none(), "no prev on first page");
+
+		// Next pages
+		let page = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			list_options(),
+			PageOptions {
+				cursor: page.next_cursor,
+				with_total: false,
+			},
+		)
+		.await?;
+		assert_eq!(page_ids(&page), ids[2..4]);
+		assert_eq!(page.total, None);
+		let page = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			list_options(),
+			PageOptions {
+				cursor: page.next_cursor,
+				with_total: false,
+			},
+		)
+		.await?;
+		assert_eq!(page_ids(&page), ids[4..5]);
+		assert!(page.next_cursor.is_none(), "no next on last page");
+
+		// Back to previous page
+		let page = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			list_options(),
+			PageOptions {
+				cursor: page.prev_cursor,
+				with_total: false,
+			},
+		)
+		.await?;
+		assert_eq!(page_ids(&page), ids[2..4]);
+		assert!(page.next_cursor.is_some() && page.prev_cursor.is_some());
+
+		// Invalid cursor
+		let res = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			list_options(),
+			PageOptions {
+				cursor: Some("not-a-cursor".to_string()),
+				with_total: false,
+			},
+		)
+		.await;
+		assert!(
+			matches!(res, Err(Error::InvalidCursor { .. })),
+			"invalid cursor {res:?}"
+		);
+
+		// Cursor with custom order_bys
+		let res = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			filters()?,
+			Some(ListOptions {
+				order_bys: Some("!title".into()),
+				..Default::default()
+			}),
+			PageOptions {
+				cursor: page.next_cursor,
+				with_total: false,
+			},
+		)
+		.await;
+		assert!(
+			matches!(res, Err(Error::InvalidCursor { .. })),
+			"cursor with order_bys {res:?}"
+		);
+
+		// Cleanup
+		for id in ids {
+			TaskBmc::delete(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_bulk_all_or_nothing() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_titles = ["test_task_bulk_1", "test_task_bulk_2"];
+
+		// Create
+		let tasks = TaskBmc::create_many(
+			&ctx,
+			&mm,
+			fx_titles
+				.iter()
+				.map(|title| TaskForCreate {
+					title: title.to_string(),
+					..Default::default()
+				})
+				.collect(),
+		)
+		.await?;
+		assert_eq!(tasks.len(), 2, "number of created tasks");
+		let ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+
+		// Update with an unknown id (nothing committed)
+		let items = [ids[0], 9999, ids[1]]
+			.into_iter()
+			.map(|id| {
+				let payload = TaskForUpdate {
+					done: Some(true),
+					..Default::default()
+				};
+				(id, None, payload)
+			})
+			.collect();
+		let res = TaskBmc::update_many(&ctx, &mm, items).await;
+		match res {
+			Err(Error::BulkFailed { entity, errors }) => {
+				assert_eq!(entity, "task");
+				assert_eq!(errors.len(), 1, "number of failed items");
+				assert_eq!(errors[0].index, 1);
+			}
+			other => panic!("should be BulkFailed {other:?}"),
+		}
+		let task = TaskBmc::get(&ctx, &mm, ids[0]).await?;
+		assert!(!task.done, "first update rolled back");
+
+		// Delete
+		TaskBmc::delete_many(&ctx, &mm, ids.clone()).await?;
+		for id in ids {
+			let res = TaskBmc::get(&ctx, &mm, id).await;
+			assert!(matches!(res, Err(Error::EntityNotFound { .. })));
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_txn_commit_and_rollback() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title = "test_task_txn";
+
+		// Rollback
+		let mm_txn = mm.new_with_txn();
+		mm_txn.begin_txn().await?;
+		let task = TaskBmc::create(
+			&ctx,
+			&mm_txn,
+			TaskForCreate {
+				title: fx_title.to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+		mm_txn.rollback_txn().await?;
+		let res = TaskBmc::get(&ctx, &mm, task.id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { .. })),
+			"rolled back {res:?}"
+		);
+
+		// Commit (nested begin, only the outermost commit commits)
+		let mm_txn = mm.new_with_txn();
+		mm_txn.begin_txn().await?;
+		mm_txn.begin_txn().await?;
+		let task = TaskBmc::create(
+			&ctx,
+			&mm_txn,
+			TaskForCreate {
+				title: fx_title.to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+		mm_txn.commit_txn().await?;
+		let res = TaskBmc::get(&ctx, &mm, task.id).await;
+		assert!(res.is_err(), "not committed before outermost commit");
+		mm_txn.commit_txn().await?;
+		let task = TaskBmc::get(&ctx, &mm, task.id).await?;
+		assert_eq!(task.title, fx_title);
+
+		// Nested rollback (only rolls back its own level)
+		let mm_txn = mm.new_with_txn();
+		mm_txn.begin_txn().await?;
+		let outer = TaskBmc::create(
+			&ctx,
+			&mm_txn,
+			TaskForCreate {
+				title: fx_title.to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+		mm_txn.begin_txn().await?;
+		let inner = TaskBmc::create(
+			&ctx,
+			&mm_txn,
+			TaskForCreate {
+				title: fx_title.to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+		mm_txn.rollback_txn().await?;
+		mm_txn.commit_txn().await?;
+		TaskBmc::get(&ctx, &mm, outer.id).await?;
+		let res = TaskBmc::get(&ctx, &mm, inner.id).await;
+		assert!(res.is_err(), "nested level rolled back {res:?}");
+
+		// No open txn (no fallback to the pool)
+		let res = TaskBmc::get(&ctx, &mm_txn, task.id).await;
+		assert!(res.is_err(), "no open txn {res:?}");
+
+		// Cleanup
+		TaskBmc::delete(&ctx, &mm, task.id).await?;
+		TaskBmc::delete(&ctx, &mm, outer.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_task_soft_delete_restore_purge() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let tasks = _dev_utils::seed_tasks(&ctx, &mm, &["test_task_trash"]).await?;
+		let id = tasks[0].id;
+		let filters = || -> Result<Option<Vec<TaskFilter>>> {
+			Ok(Some(vec![serde_json::from_value(
+				json!({"title": "test_task_trash"}),
+			)?]))
+		};
+
+		// Soft delete
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		let res = TaskBmc::get(&ctx, &mm, id).await;
+		assert!(matches!(res, Err(Error::EntityNotFound { .. })), "{res:?}");
+		let tasks = TaskBmc::list(&ctx, &mm, filters()?, None).await?;
+		assert!(tasks.is_empty(), "deleted task not listed");
+		let page = TaskBmc::list_deleted_page(
+			&ctx,
+			&mm,
+			filters()?,
+			None,
+			PageOptions::default(),
+		)
+		.await?;
+		assert_eq!(page.items.len(), 1, "number of deleted tasks");
+		assert!(page.items[0].deleted_at.is_some());
+
+		// Restore
+		let task = TaskBmc::restore(&ctx, &mm, id).await?;
+		assert!(task.deleted_at.is_none());
+
+		// Purge (only the deleted ones)
+		TaskBmc::purge_deleted(&ctx, &mm, Duration::ZERO).await?;
+		TaskBmc::get(&ctx, &mm, id).await?;
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		let count = TaskBmc::purge_deleted(&ctx, &mm, Duration::ZERO).await?;
+		assert!(count >= 1, "deleted task purged");
+		let res = TaskBmc::restore(&ctx, &mm, id).await;
+		assert!(matches!(res, Err(Error::EntityNotFound { .. })), "{res:?}");
+
+		Ok(())
+	}
 }
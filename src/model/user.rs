@@ -1,6 +1,9 @@
 use super::{base, Error, Result};
-use modql::field::{Fields, HasFields};
-use sea_query::{Expr, Iden, IntoIden, PostgresQueryBuilder, Query, SimpleExpr};
+use modql::{
+	field::{Fields, HasFields},
+	filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString},
+};
+use sea_query::{Expr, Iden, IntoIden, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgRow, prelude::FromRow};
@@ -11,18 +14,22 @@ use crate::{
 	ctx::Ctx,
 };
 
+use super::page::{HasId, Page, PageOptions};
 use super::{base::DbBmc, ModelManager};
 
 #[derive(Clone, Debug, Fields, FromRow, Serialize)]
 pub struct User {
 	pub id: i64,
 	pub username: String,
+	pub admin: bool,
+	pub disabled: bool,
+	pub version: i64,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UserForCreate {
-	pub username: String,
-	pub password: String,
+impl HasId for User {
+	fn id(&self) -> i64 {
+		self.id
+	}
 }
 
 #[derive(Fields)]
@@ -30,6 +37,18 @@ struct UserForInsert {
 	username: String,
 }
 
+#[derive(Deserialize, Fields, Default)]
+pub struct UserForUpdate {
+	pub username: Option<String>,
+	pub admin: Option<bool>,
+	pub disabled: Option<bool>,
+}
+
+#[derive(Fields)]
+struct UserForUpdatePwd {
+	pwd: String,
+}
+
 #[derive(Clone, Debug, Fields, FromRow)]
 pub struct UserForLogin {
 	pub id: i64,
@@ -37,6 +56,7 @@ pub struct UserForLogin {
 	pub pwd: Option<String>,
 	pub pwd_salt: Uuid,
 	pub token_salt: Uuid,
+	pub disabled: bool,
 }
 
 #[derive(Clone, Debug, Fields, FromRow)]
@@ -44,6 +64,16 @@ pub struct UserForAuth {
 	pub id: i64,
 	pub username: String,
 	pub token_salt: Uuid,
+	pub admin: bool,
+	pub disabled: bool,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct UserFilter {
+	id: Option<OpValsInt64>,
+	username: Option<OpValsString>,
+	admin: Option<OpValsBool>,
+	disabled: Option<OpValsBool>,
 }
 
 pub trait UserBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
@@ -52,7 +82,6 @@ pub trait UserBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 enum UserIden {
 	Id,
 	Username,
-	Pwd,
 }
 
 impl UserBy for User {}
@@ -66,6 +95,17 @@ impl DbBmc for UserBmc {
 }
 
 impl UserBmc {
+	/// User management is reserved to admin contexts (root ctx included).
+	pub fn ensure_admin(ctx: &Ctx) -> Result<()> {
+		if !ctx.is_admin() {
+			return Err(Error::AccessDenied {
+				entity: Self::TABLE,
+				user_id: ctx.user_id(),
+			});
+		}
+		Ok(())
+	}
+
 	pub async fn get<U>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<U>
 	where
 		U: UserBy,
@@ -73,6 +113,74 @@ impl UserBmc {
 		base::get::<Self, _>(ctx, mm, id).await
 	}
 
+	/// Same as `get`, for the user management (admin only).
+	pub async fn get_for_admin(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<User> {
+		Self::ensure_admin(ctx)?;
+		Self::get(ctx, mm, id).await
+	}
+
+	pub async fn list(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<UserFilter>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<User>> {
+		Self::ensure_admin(ctx)?;
+		base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+	}
+
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filters: Option<Vec<UserFilter>>,
+		list_options: Option<ListOptions>,
+		page_options: PageOptions,
+	) -> Result<Page<User>> {
+		Self::ensure_admin(ctx)?;
+		base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options)
+			.await
+	}
+
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+		payload: UserForUpdate,
+	) -> Result<User> {
+		Self::ensure_admin(ctx)?;
+		base::update::<Self, _, UserForUpdate>(ctx, mm, id, version, payload).await
+	}
+
+	/// See `base::update` for the `version`.
+	pub async fn disable(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+	) -> Result<User> {
+		Self::update(
+			ctx,
+			mm,
+			id,
+			version,
+			UserForUpdate {
+				disabled: Some(true),
+				..Default::default()
+			},
+		)
+		.await
+	}
+
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		Self::ensure_admin(ctx)?;
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
 	pub async fn first_by_username<U>(
 		ctx: &Ctx,
 		mm: &ModelManager,
@@ -81,7 +189,7 @@ impl UserBmc {
 	where
 		U: UserBy,
 	{
-		let db = mm.db();
+		let dbx = mm.dbx();
 		// build query
 		let mut query = Query::select();
 		query
@@ -91,20 +199,20 @@ impl UserBmc {
 
 		// exec query
 		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-		let user = sqlx::query_as_with::<_, U, _>(&sql, values)
-			.fetch_optional(db)
-			.await?;
+		let sqlx_query = sqlx::query_as_with::<_, U, _>(&sql, values);
+		let user = dbx.fetch_optional(sqlx_query).await?;
 
 		Ok(user)
 	}
 
+	/// Note: An update (its `version` incremented), without version check
+	///       (server side, the clients do not read the pwd).
 	pub async fn update_pwd(
 		ctx: &Ctx,
 		mm: &ModelManager,
 		id: i64,
 		pwd_clear: &str,
 	) -> Result<()> {
-		let db = mm.db();
 		let user: UserForLogin = Self::get(ctx, mm, id).await?;
 
 		// encrypt pwd
@@ -113,26 +221,10 @@ impl UserBmc {
 			salt: user.pwd_salt.to_string(),
 		})?;
 
-		// build query
-		let mut query = Query::update();
-		query
-			.table(Self::table_ref())
-			.value(UserIden::Pwd, SimpleExpr::from(pwd))
-			.and_where(Expr::col(UserIden::Id).eq(id));
-
-		// exec query
-		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-		let count = sqlx::query_with(&sql, values)
-			.execute(db)
-			.await?
-			.rows_affected();
-
-    if count != 1 {
-      return Err(Error::UpdateFailed {
-        entity: Self::TABLE,
-        id,
-      });
-    }
+		// (the `User` returned, hence the history changes, has no pwd)
+		let _: User =
+			base::update::<Self, _, _>(ctx, mm, id, None, UserForUpdatePwd { pwd })
+				.await?;
 
 		Ok(())
 	}
@@ -153,6 +245,9 @@ mod tests {
 		let demo_user = User {
 			id: 1000,
 			username: "demo1".to_string(),
+			admin: false,
+			disabled: false,
+			version: 1,
 		};
 
 		// Get root user
@@ -172,6 +267,9 @@ mod tests {
 		let demo_user = User {
 			id: 1000,
 			username: "demo1".to_string(),
+			admin: false,
+			disabled: false,
+			version: 1,
 		};
 
 		// Get root user
@@ -193,11 +291,17 @@ mod tests {
 		let fx_user = User {
 			id: 1000,
 			username: "demo1".to_string(),
+			admin: false,
+			disabled: false,
+			version: 1,
 		};
     let fx_pwd = "123456";
+		let before: User = UserBmc::get(&ctx, &mm, fx_user.id).await?;
     
     // Update pwd
     UserBmc::update_pwd(&ctx, &mm, fx_user.id, fx_pwd).await?;
+		let after: User = UserBmc::get(&ctx, &mm, fx_user.id).await?;
+		assert_eq!(after.version, before.version + 1, "version incremented");
     
 		// Get root user
 		let user =
@@ -214,4 +318,98 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_users_by_username() -> Result<()> {
+		let ctx = Ctx::root_ctx();
+		let mm = _dev_utils::init_test().await;
+
+		// Execute
+		let users = UserBmc::list(
+			&ctx,
+			&mm,
+			Some(vec![UserFilter {
+				username: Some("demo1".into()),
+				..Default::default()
+			}]),
+			None,
+		)
+		.await?;
+
+		// Check
+		assert_eq!(users.len(), 1, "number of demo1 users");
+		assert_eq!(users[0].username, "demo1");
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_users_not_admin_denied() -> Result<()> {
+		let ctx = Ctx::new(1000)?;
+		let mm = _dev_utils::init_test().await;
+
+		// Execute
+		let res = UserBmc::list(&ctx, &mm, None, None).await;
+
+		// Check
+		assert!(
+			matches!(res, Err(Error::AccessDenied { entity: "user", user_id: 1000 })),
+			"should be denied {res:?}"
+		);
+		let res = UserBmc::get_for_admin(&ctx, &mm, 1000).await;
+		assert!(
+			matches!(res, Err(Error::AccessDenied { .. })),
+			"get should be denied {res:?}"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_disable_delete_user() -> Result<()> {
+		let ctx = Ctx::root_ctx();
+		let mm = _dev_utils::init_test().await;
+		let fx_username = "test_update_disable_delete_user";
+		let user: User = base::create::<UserBmc, _, _>(
+			&ctx,
+			&mm,
+			UserForInsert {
+				username: fx_username.to_string(),
+			},
+		)
+		.await?;
+		let id = user.id;
+
+		// Rename
+		let user = UserBmc::update(
+			&ctx,
+			&mm,
+			id,
+			Some(user.version),
+			UserForUpdate {
+				username: Some(format!("{fx_username}_renamed")),
+				..Default::default()
+			},
+		)
+		.await?;
+		assert_eq!(user.username, format!("{fx_username}_renamed"));
+		assert!(!user.disabled);
+
+		// Disable
+		let user = UserBmc::disable(&ctx, &mm, id, Some(user.version)).await?;
+		assert!(user.disabled);
+
+		// Delete
+		UserBmc::delete(&ctx, &mm, id).await?;
+		let res = UserBmc::get::<User>(&ctx, &mm, id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { entity: "user", .. })),
+			"should be deleted {res:?}"
+		);
+
+		Ok(())
+	}
 }
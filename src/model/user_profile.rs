@@ -0,0 +1,144 @@
+use modql::field::Fields;
+use sea_query::{Iden, OnConflict, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use time::OffsetDateTime;
+
+use super::base::{self, DbBmc};
+use super::{ModelManager, Result};
+use crate::ctx::Ctx;
+use crate::utils::serialize_time;
+
+/// Profile of a user, the `id` is the `user.id` (one to one).
+#[derive(Clone, Debug, Fields, FromRow, Serialize)]
+pub struct UserProfile {
+	pub id: i64,
+	pub display_name: Option<String>,
+	pub email: Option<String>,
+	#[serde(serialize_with = "serialize_time")]
+	pub ctime: OffsetDateTime,
+	pub version: i64,
+}
+
+#[derive(Deserialize, Fields, Default)]
+pub struct UserProfileForUpdate {
+	pub display_name: Option<String>,
+	pub email: Option<String>,
+}
+
+#[derive(Iden)]
+enum UserProfileIden {
+	Id,
+}
+
+pub struct UserProfileBmc;
+
+impl DbBmc for UserProfileBmc {
+	const TABLE: &'static str = "user_profile";
+}
+
+impl UserProfileBmc {
+	/// Get the profile of the user `id`, creating the empty profile row
+	/// on first access.
+	pub async fn get_or_init(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<UserProfile> {
+		let dbx = mm.dbx();
+
+		// build query
+		let mut query = Query::insert();
+		query
+			.into_table(Self::table_ref())
+			.columns([UserProfileIden::Id])
+			.values([id.into()])?
+			.on_conflict(
+				OnConflict::column(UserProfileIden::Id)
+					.do_nothing()
+					.to_owned(),
+			);
+
+		// exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_with(&sql, values);
+		dbx.execute(sqlx_query).await?;
+
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// See `base::update` for the `version`.
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		version: Option<i64>,
+		payload: UserProfileForUpdate,
+	) -> Result<UserProfile> {
+		Self::get_or_init(ctx, mm, id).await?;
+		base::update::<Self, _, UserProfileForUpdate>(ctx, mm, id, version, payload)
+			.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_user_profile_get_or_init_and_update() -> Result<()> {
+		let ctx = Ctx::root_ctx();
+		let mm = _dev_utils::init_test().await;
+		let fx_user_id = 1000;
+		let fx_display_name = "Demo One";
+
+		// Get (init)
+		let profile = UserProfileBmc::get_or_init(&ctx, &mm, fx_user_id).await?;
+		assert_eq!(profile.id, fx_user_id);
+
+		// Update
+		let updated = UserProfileBmc::update(
+			&ctx,
+			&mm,
+			fx_user_id,
+			Some(profile.version),
+			UserProfileForUpdate {
+				display_name: Some(fx_display_name.to_string()),
+				..Default::default()
+			},
+		)
+		.await?;
+
+		// Check
+		assert_eq!(updated.display_name.as_deref(), Some(fx_display_name));
+		assert_eq!(updated.ctime, profile.ctime, "ctime should not change");
+		assert_eq!(updated.version, profile.version + 1, "version incremented");
+
+		// Update at a stale version
+		let res = UserProfileBmc::update(
+			&ctx,
+			&mm,
+			fx_user_id,
+			Some(profile.version),
+			UserProfileForUpdate::default(),
+		)
+		.await;
+		assert!(
+			matches!(
+				res,
+				Err(crate::model::Error::VersionConflict {
+					entity: "user_profile",
+					..
+				})
+			),
+			"stale version {res:?}"
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,69 @@
+use crate::{
+	ctx::Ctx,
+	model::{
+		comment::{
+			Comment, CommentBmc, CommentFilter, CommentForCreate, CommentForUpdate,
+		},
+		page::Page,
+		ModelManager,
+	},
+};
+
+use super::{ParamsForCreate, ParamsForUpdate, ParamsId, ParamsList, Result};
+
+pub async fn add_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<CommentForCreate>,
+) -> Result<Comment> {
+	let ParamsForCreate { data } = params;
+	let comment = CommentBmc::create(&ctx, &mm, data).await?;
+	Ok(comment)
+}
+
+pub async fn list_comments(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsList<CommentFilter>,
+) -> Result<Page<Comment>> {
+	let ParamsList {
+		filters,
+		list_options,
+		page_options,
+	} = params;
+	let comments =
+		CommentBmc::list_page(&ctx, &mm, filters, list_options, page_options)
+			.await?;
+	Ok(comments)
+}
+
+pub async fn update_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<CommentForUpdate>,
+) -> Result<Comment> {
+	let ParamsForUpdate { id, version, data } = params;
+	let comment = CommentBmc::update(&ctx, &mm, id, Some(version), data).await?;
+	Ok(comment)
+}
+
+pub async fn delete_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Comment> {
+	let ParamsId { id } = params;
+
+	// get and delete in the same transaction
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+	let res = async {
+		let comment = CommentBmc::get(&ctx, &mm, id).await?;
+		CommentBmc::delete(&ctx, &mm, id).await?;
+		Ok::<_, crate::model::Error>(comment)
+	}
+	.await;
+	let comment = mm.end_txn(res).await?;
+
+	Ok(comment)
+}
@@ -0,0 +1,111 @@
+use serde::Deserialize;
+
+use crate::{
+	ctx::Ctx,
+	model::{
+		label::{Label, LabelBmc, LabelFilter, LabelForCreate, LabelForUpdate},
+		page::Page,
+		ModelManager,
+	},
+};
+
+use super::{ParamsForCreate, ParamsForUpdate, ParamsId, ParamsList, Result};
+
+#[derive(Deserialize)]
+pub struct ParamsTaskLabel {
+	pub task_id: i64,
+	pub label_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ParamsTaskId {
+	pub task_id: i64,
+}
+
+pub async fn create_label(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<LabelForCreate>,
+) -> Result<Label> {
+	let ParamsForCreate { data } = params;
+	let label = LabelBmc::create(&ctx, &mm, data).await?;
+	Ok(label)
+}
+
+pub async fn list_labels(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsList<LabelFilter>,
+) -> Result<Page<Label>> {
+	let ParamsList {
+		filters,
+		list_options,
+		page_options,
+	} = params;
+	let labels =
+		LabelBmc::list_page(&ctx, &mm, filters, list_options, page_options).await?;
+	Ok(labels)
+}
+
+pub async fn update_label(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<LabelForUpdate>,
+) -> Result<Label> {
+	let ParamsForUpdate { id, version, data } = params;
+	let label = LabelBmc::update(&ctx, &mm, id, Some(version), data).await?;
+	Ok(label)
+}
+
+pub async fn delete_label(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Label> {
+	let ParamsId { id } = params;
+
+	// get and delete in the same transaction
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+	let res = async {
+		let label = LabelBmc::get(&ctx, &mm, id).await?;
+		LabelBmc::delete(&ctx, &mm, id).await?;
+		Ok::<_, crate::model::Error>(label)
+	}
+	.await;
+	let label = mm.end_txn(res).await?;
+
+	Ok(label)
+}
+
+pub async fn attach_label(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsTaskLabel,
+) -> Result<Vec<Label>> {
+	let ParamsTaskLabel { task_id, label_id } = params;
+	LabelBmc::attach(&ctx, &mm, task_id, label_id).await?;
+	let labels = LabelBmc::list_for_task(&ctx, &mm, task_id).await?;
+	Ok(labels)
+}
+
+pub async fn detach_label(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsTaskLabel,
+) -> Result<Vec<Label>> {
+	let ParamsTaskLabel { task_id, label_id } = params;
+	LabelBmc::detach(&ctx, &mm, task_id, label_id).await?;
+	let labels = LabelBmc::list_for_task(&ctx, &mm, task_id).await?;
+	Ok(labels)
+}
+
+pub async fn list_task_labels(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsTaskId,
+) -> Result<Vec<Label>> {
+	let ParamsTaskId { task_id } = params;
+	let labels = LabelBmc::list_for_task(&ctx, &mm, task_id).await?;
+	Ok(labels)
+}
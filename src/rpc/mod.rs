@@ -8,12 +8,18 @@ use modql::filter::ListOptions;
 use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::{from_value, json, to_value, Value};
 use serde_with::{serde_as, OneOrMany};
-use tracing::debug;
+use tracing::{debug, info_span, Instrument};
 
+mod comment_rpc;
+mod label_rpc;
 mod task_rpc;
+mod user_rpc;
+
+pub use self::user_rpc::Me;
+
 use crate::{
 	ctx::Ctx,
-	model::ModelManager,
+	model::{page::PageOptions, ModelManager},
 	web::{Error, Result},
 };
 
@@ -35,9 +41,17 @@ pub struct ParamsForCreate<T> {
 	pub data: T,
 }
 
+#[derive(Deserialize)]
+pub struct ParamsData<T> {
+	pub data: T,
+}
+
+/// `version` is the version of the entity the client read (otherwise the
+/// update fails with a version conflict, and the current entity).
 #[derive(Deserialize)]
 pub struct ParamsForUpdate<T> {
 	pub id: i64,
+	pub version: i64,
 	pub data: T,
 }
 
@@ -46,6 +60,11 @@ pub struct ParamsId {
 	pub id: i64,
 }
 
+#[derive(Deserialize)]
+pub struct ParamsIds {
+	pub ids: Vec<i64>,
+}
+
 #[serde_as]
 #[derive(Deserialize)]
 pub struct ParamsList<F>
@@ -55,6 +74,13 @@ where
 	#[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
 	pub filters: Option<Vec<F>>,
 	pub list_options: Option<ListOptions>,
+	/// `cursor` and `with_total` of the returned `Page`.
+	///
+	/// Note: a custom `list_options.order_bys` disables the cursors (the page
+	///       is offset based, without `next_cursor`/`prev_cursor`), and a
+	///       `cursor` with it is rejected (`INVALID_CURSOR`).
+	#[serde(flatten)]
+	pub page_options: PageOptions,
 }
 
 async fn rpc_handler(
@@ -66,7 +92,15 @@ async fn rpc_handler(
 		id: rpc_request.id.clone(),
 		method: rpc_request.method.clone(),
 	};
-	let mut res = _rpc_handler(ctx, mm, rpc_request).await.into_response();
+	let span = info_span!(
+		"rpc",
+		rpc.method = %rpc_info.method,
+		rpc.id = ?rpc_info.id,
+	);
+	let mut res = _rpc_handler(ctx, mm, rpc_request)
+		.instrument(span)
+		.await
+		.into_response();
 	res.extensions_mut().insert(rpc_info);
 	res
 }
@@ -101,9 +135,62 @@ async fn _rpc_handler(
 	let result = match method.as_str() {
 		"list_tasks" => exec_rpc_fn!(task_rpc::list_tasks, ctx, mm, params),
 		"get_task" => exec_rpc_fn!(task_rpc::get_task, ctx, mm, params),
+		"search_tasks" => exec_rpc_fn!(task_rpc::search_tasks, ctx, mm, params),
+		"get_task_history" => {
+			exec_rpc_fn!(task_rpc::get_task_history, ctx, mm, params)
+		}
 		"create_task" => exec_rpc_fn!(task_rpc::create_task, ctx, mm, params),
 		"update_task" => exec_rpc_fn!(task_rpc::update_task, ctx, mm, params),
 		"delete_task" => exec_rpc_fn!(task_rpc::delete_task, ctx, mm, params),
+		"restore_task" => exec_rpc_fn!(task_rpc::restore_task, ctx, mm, params),
+		"list_deleted_tasks" => {
+			exec_rpc_fn!(task_rpc::list_deleted_tasks, ctx, mm, params)
+		}
+		"purge_deleted_tasks" => {
+			exec_rpc_fn!(task_rpc::purge_deleted_tasks, ctx, mm, params)
+		}
+		"assign_task" => exec_rpc_fn!(task_rpc::assign_task, ctx, mm, params),
+		"watch_task" => exec_rpc_fn!(task_rpc::watch_task, ctx, mm, params),
+		"unwatch_task" => exec_rpc_fn!(task_rpc::unwatch_task, ctx, mm, params),
+		"create_tasks" => exec_rpc_fn!(task_rpc::create_tasks, ctx, mm, params),
+		"update_tasks" => exec_rpc_fn!(task_rpc::update_tasks, ctx, mm, params),
+		"delete_tasks" => exec_rpc_fn!(task_rpc::delete_tasks, ctx, mm, params),
+		"link_subtask" => exec_rpc_fn!(task_rpc::link_subtask, ctx, mm, params),
+		"unlink_subtask" => exec_rpc_fn!(task_rpc::unlink_subtask, ctx, mm, params),
+		"link_task_dependency" => {
+			exec_rpc_fn!(task_rpc::link_task_dependency, ctx, mm, params)
+		}
+		"unlink_task_dependency" => {
+			exec_rpc_fn!(task_rpc::unlink_task_dependency, ctx, mm, params)
+		}
+		"get_task_tree" => exec_rpc_fn!(task_rpc::get_task_tree, ctx, mm, params),
+		// -- Comment RPC methods
+		"add_comment" => exec_rpc_fn!(comment_rpc::add_comment, ctx, mm, params),
+		"list_comments" => exec_rpc_fn!(comment_rpc::list_comments, ctx, mm, params),
+		"update_comment" => {
+			exec_rpc_fn!(comment_rpc::update_comment, ctx, mm, params)
+		}
+		"delete_comment" => {
+			exec_rpc_fn!(comment_rpc::delete_comment, ctx, mm, params)
+		}
+		// -- Label RPC methods
+		"create_label" => exec_rpc_fn!(label_rpc::create_label, ctx, mm, params),
+		"list_labels" => exec_rpc_fn!(label_rpc::list_labels, ctx, mm, params),
+		"update_label" => exec_rpc_fn!(label_rpc::update_label, ctx, mm, params),
+		"delete_label" => exec_rpc_fn!(label_rpc::delete_label, ctx, mm, params),
+		"attach_label" => exec_rpc_fn!(label_rpc::attach_label, ctx, mm, params),
+		"detach_label" => exec_rpc_fn!(label_rpc::detach_label, ctx, mm, params),
+		"list_task_labels" => {
+			exec_rpc_fn!(label_rpc::list_task_labels, ctx, mm, params)
+		}
+		"get_me" => exec_rpc_fn!(user_rpc::get_me, ctx, mm),
+		"update_me" => exec_rpc_fn!(user_rpc::update_me, ctx, mm, params),
+		// -- User RPC methods (admin only)
+		"list_users" => exec_rpc_fn!(user_rpc::list_users, ctx, mm, params),
+		"get_user" => exec_rpc_fn!(user_rpc::get_user, ctx, mm, params),
+		"update_user" => exec_rpc_fn!(user_rpc::update_user, ctx, mm, params),
+		"disable_user" => exec_rpc_fn!(user_rpc::disable_user, ctx, mm, params),
+		"delete_user" => exec_rpc_fn!(user_rpc::delete_user, ctx, mm, params),
 		_ => return Err(Error::RpcMethodNotFound(method)),
 	};
 
@@ -1,20 +1,89 @@
+use modql::filter::ListOptions;
+use serde::Deserialize;
+use serde_with::{serde_as, OneOrMany};
+use time::Duration;
+
 use crate::{
 	ctx::Ctx,
 	model::{
-		task::{Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate},
+		entity_history::EntityHistory,
+		page::{Page, PageOptions},
+		task::{
+			Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate, TaskSearchHit,
+			TaskTree,
+		},
 		ModelManager,
 	},
 };
 
-use super::{ParamsForCreate, ParamsForUpdate, ParamsId, ParamsList, Result};
+use super::{
+	ParamsData, ParamsForCreate, ParamsForUpdate, ParamsId, ParamsIds, ParamsList,
+	Result,
+};
+
+/// `query` is a websearch query (e.g., `"quoted phrase" or -excluded`),
+/// the hits are ordered by rank (no `order_bys`).
+///
+/// Note: The returned `Page` is offset based (`limit`, `offset`), without
+///       cursors (`with_total` for the number of hits).
+#[serde_as]
+#[derive(Deserialize)]
+pub struct ParamsForSearch {
+	pub query: String,
+	#[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
+	pub filters: Option<Vec<TaskFilter>>,
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+	#[serde(flatten)]
+	pub page_options: PageOptions,
+}
+
+/// `version` as in `ParamsForUpdate` (not checked when absent).
+#[derive(Deserialize)]
+pub struct ParamsForAssign {
+	pub id: i64,
+	pub version: Option<i64>,
+	pub assignee_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ParamsForHistory {
+	pub id: i64,
+	pub list_options: Option<ListOptions>,
+	/// See `ParamsList::page_options`.
+	#[serde(flatten)]
+	pub page_options: PageOptions,
+}
+
+#[derive(Deserialize)]
+pub struct ParamsForPurge {
+	pub older_than_days: u32,
+}
+
+#[derive(Deserialize)]
+pub struct ParamsForSubtask {
+	pub id: i64,
+	pub parent_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ParamsForDependency {
+	pub id: i64,
+	pub depends_on_id: i64,
+}
 
 pub async fn list_tasks(
 	ctx: Ctx,
 	mm: ModelManager,
 	params: ParamsList<TaskFilter>,
-) -> Result<Vec<Task>> {
+) -> Result<Page<Task>> {
+	let ParamsList {
+		filters,
+		list_options,
+		page_options,
+	} = params;
 	let tasks =
-		TaskBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+		TaskBmc::list_page(&ctx, &mm, filters, list_options, page_options).await?;
 	Ok(tasks)
 }
 
@@ -24,6 +93,50 @@ pub async fn get_task(ctx: Ctx, mm: ModelManager, params: ParamsId) -> Result<Ta
 	Ok(task)
 }
 
+pub async fn search_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForSearch,
+) -> Result<Page<TaskSearchHit>> {
+	let ParamsForSearch {
+		query,
+		filters,
+		limit,
+		offset,
+		page_options,
+	} = params;
+	let list_options = ListOptions {
+		limit,
+		offset,
+		order_bys: None,
+	};
+	let hits = TaskBmc::search(
+		&ctx,
+		&mm,
+		&query,
+		filters,
+		Some(list_options),
+		page_options,
+	)
+	.await?;
+	Ok(hits)
+}
+
+pub async fn get_task_history(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForHistory,
+) -> Result<Page<EntityHistory>> {
+	let ParamsForHistory {
+		id,
+		list_options,
+		page_options,
+	} = params;
+	let history =
+		TaskBmc::history(&ctx, &mm, id, list_options, page_options).await?;
+	Ok(history)
+}
+
 pub async fn create_task(
 	ctx: Ctx,
 	mm: ModelManager,
@@ -39,8 +152,8 @@ pub async fn update_task(
 	mm: ModelManager,
 	params: ParamsForUpdate<TaskForUpdate>,
 ) -> Result<Task> {
-	let ParamsForUpdate { id, data } = params;
-	let task = TaskBmc::update(&ctx, &mm, id, data).await?;
+	let ParamsForUpdate { id, version, data } = params;
+	let task = TaskBmc::update(&ctx, &mm, id, Some(version), data).await?;
 	Ok(task)
 }
 
@@ -50,7 +163,178 @@ pub async fn delete_task(
 	params: ParamsId,
 ) -> Result<Task> {
 	let ParamsId { id } = params;
+
+	// get and delete in the same transaction
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+	let res = async {
+		let task = TaskBmc::get(&ctx, &mm, id).await?;
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		Ok::<_, crate::model::Error>(task)
+	}
+	.await;
+	let task = mm.end_txn(res).await?;
+
+	Ok(task)
+}
+
+pub async fn restore_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Task> {
+	let ParamsId { id } = params;
+	let task = TaskBmc::restore(&ctx, &mm, id).await?;
+	Ok(task)
+}
+
+pub async fn list_deleted_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsList<TaskFilter>,
+) -> Result<Page<Task>> {
+	let ParamsList {
+		filters,
+		list_options,
+		page_options,
+	} = params;
+	let tasks =
+		TaskBmc::list_deleted_page(&ctx, &mm, filters, list_options, page_options)
+			.await?;
+	Ok(tasks)
+}
+
+/// Returns the number of purged tasks.
+pub async fn purge_deleted_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForPurge,
+) -> Result<u64> {
+	let ParamsForPurge { older_than_days } = params;
+	let older_than = Duration::days(older_than_days.into());
+	let count = TaskBmc::purge_deleted(&ctx, &mm, older_than).await?;
+	Ok(count)
+}
+
+/// All or nothing, see `model::Error::BulkFailed` for the failing items.
+pub async fn create_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsData<Vec<TaskForCreate>>,
+) -> Result<Vec<Task>> {
+	let ParamsData { data } = params;
+	let tasks = TaskBmc::create_many(&ctx, &mm, data).await?;
+	Ok(tasks)
+}
+
+pub async fn update_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsData<Vec<ParamsForUpdate<TaskForUpdate>>>,
+) -> Result<Vec<Task>> {
+	let ParamsData { data } = params;
+	let items = data
+		.into_iter()
+		.map(|p| (p.id, Some(p.version), p.data))
+		.collect();
+	let tasks = TaskBmc::update_many(&ctx, &mm, items).await?;
+	Ok(tasks)
+}
+
+pub async fn delete_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIds,
+) -> Result<Vec<i64>> {
+	let ParamsIds { ids } = params;
+	TaskBmc::delete_many(&ctx, &mm, ids.clone()).await?;
+	Ok(ids)
+}
+
+pub async fn assign_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForAssign,
+) -> Result<Task> {
+	let ParamsForAssign {
+		id,
+		version,
+		assignee_id,
+	} = params;
+	let task = TaskBmc::assign(&ctx, &mm, id, version, assignee_id).await?;
+	Ok(task)
+}
+
+pub async fn watch_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Task> {
+	let ParamsId { id } = params;
+	TaskBmc::add_watcher(&ctx, &mm, id, ctx.user_id()).await?;
+	let task = TaskBmc::get(&ctx, &mm, id).await?;
+	Ok(task)
+}
+
+pub async fn unwatch_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Task> {
+	let ParamsId { id } = params;
+	TaskBmc::remove_watcher(&ctx, &mm, id, ctx.user_id()).await?;
 	let task = TaskBmc::get(&ctx, &mm, id).await?;
-	TaskBmc::delete(&ctx, &mm, id).await?;
 	Ok(task)
 }
+
+pub async fn link_subtask(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForSubtask,
+) -> Result<Task> {
+	let ParamsForSubtask { id, parent_id } = params;
+	let task = TaskBmc::set_parent(&ctx, &mm, id, Some(parent_id)).await?;
+	Ok(task)
+}
+
+pub async fn unlink_subtask(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<Task> {
+	let ParamsId { id } = params;
+	let task = TaskBmc::set_parent(&ctx, &mm, id, None).await?;
+	Ok(task)
+}
+
+pub async fn link_task_dependency(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForDependency,
+) -> Result<TaskTree> {
+	let ParamsForDependency { id, depends_on_id } = params;
+	TaskBmc::add_dependency(&ctx, &mm, id, depends_on_id).await?;
+	let tree = TaskBmc::get_tree(&ctx, &mm, id).await?;
+	Ok(tree)
+}
+
+pub async fn unlink_task_dependency(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForDependency,
+) -> Result<TaskTree> {
+	let ParamsForDependency { id, depends_on_id } = params;
+	TaskBmc::remove_dependency(&ctx, &mm, id, depends_on_id).await?;
+	let tree = TaskBmc::get_tree(&ctx, &mm, id).await?;
+	Ok(tree)
+}
+
+pub async fn get_task_tree(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<TaskTree> {
+	let ParamsId { id } = params;
+	let tree = TaskBmc::get_tree(&ctx, &mm, id).await?;
+	Ok(tree)
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ctx::Ctx,
+	model::{
+		self,
+		page::Page,
+		user::{User, UserBmc, UserFilter, UserForUpdate},
+		user_profile::{UserProfile, UserProfileBmc, UserProfileForUpdate},
+		ModelManager,
+	},
+};
+
+use super::{ParamsForUpdate, ParamsId, ParamsList, Result};
+
+/// `version` as in `ParamsForUpdate` (not checked when absent).
+#[derive(Deserialize)]
+pub struct ParamsForDisable {
+	pub id: i64,
+	pub version: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct Me {
+	#[serde(flatten)]
+	pub user: User,
+	pub profile: UserProfile,
+}
+
+impl Me {
+	/// The user `user_id` with its profile (e.g., for the login response).
+	pub async fn load(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<Self> {
+		let user: User = UserBmc::get(ctx, mm, user_id).await?;
+		let profile = UserProfileBmc::get_or_init(ctx, mm, user.id).await?;
+		Ok(Me { user, profile })
+	}
+}
+
+pub async fn get_me(ctx: Ctx, mm: ModelManager) -> Result<Me> {
+	Me::load(&ctx, &mm, ctx.user_id()).await
+}
+
+/// `id` must be the ctx user (the `id` of its profile).
+pub async fn update_me(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<UserProfileForUpdate>,
+) -> Result<Me> {
+	let ParamsForUpdate { id, version, data } = params;
+	if id != ctx.user_id() {
+		return Err(model::Error::AccessDenied {
+			entity: "user_profile",
+			user_id: ctx.user_id(),
+		}
+		.into());
+	}
+	let user: User = UserBmc::get(&ctx, &mm, id).await?;
+	let profile =
+		UserProfileBmc::update(&ctx, &mm, user.id, Some(version), data).await?;
+	Ok(Me { user, profile })
+}
+
+pub async fn list_users(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsList<UserFilter>,
+) -> Result<Page<User>> {
+	let ParamsList {
+		filters,
+		list_options,
+		page_options,
+	} = params;
+	let users =
+		UserBmc::list_page(&ctx, &mm, filters, list_options, page_options).await?;
+	Ok(users)
+}
+
+pub async fn get_user(ctx: Ctx, mm: ModelManager, params: ParamsId) -> Result<User> {
+	let ParamsId { id } = params;
+	let user = UserBmc::get_for_admin(&ctx, &mm, id).await?;
+	Ok(user)
+}
+
+pub async fn update_user(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<UserForUpdate>,
+) -> Result<User> {
+	let ParamsForUpdate { id, version, data } = params;
+	let user = UserBmc::update(&ctx, &mm, id, Some(version), data).await?;
+	Ok(user)
+}
+
+pub async fn disable_user(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForDisable,
+) -> Result<User> {
+	let ParamsForDisable { id, version } = params;
+	let user = UserBmc::disable(&ctx, &mm, id, version).await?;
+	Ok(user)
+}
+
+pub async fn delete_user(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsId,
+) -> Result<User> {
+	let ParamsId { id } = params;
+	UserBmc::ensure_admin(&ctx)?;
+
+	// get and delete in the same transaction
+	let mm = mm.new_with_txn();
+	mm.begin_txn().await?;
+	let res = async {
+		let user = UserBmc::get(&ctx, &mm, id).await?;
+		UserBmc::delete(&ctx, &mm, id).await?;
+		Ok::<_, crate::model::Error>(user)
+	}
+	.await;
+	let user = mm.end_txn(res).await?;
+
+	Ok(user)
+}
@@ -0,0 +1,192 @@
+//! The tracing subscriber: the fmt output (filtered by `RUST_LOG`), and,
+//! when `OTLP_ENDPOINT` is configured, the export of the spans of this
+//! crate to an OpenTelemetry collector (otlp over http).
+//!
+//! The spans: `request` (see `web::mw_req_stamp`, continuing the incoming
+//! W3C `traceparent`), `rpc` (the rpc method dispatch) and `db.query` (each
+//! query of `model::store::dbx`).
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::config::Config;
+use axum::http::HeaderMap;
+use hyper::Client;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_http::hyper::HyperClient;
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace as sdktrace, Resource};
+use std::time::Duration;
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+// endregion: --- Modules
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn init_tracing(config: &Config) -> Result<()> {
+	let fmt_layer = tracing_subscriber::fmt::layer()
+		.without_time() // For early local development
+		.with_target(false)
+		.with_filter(EnvFilter::from_default_env());
+
+	// Note: Not filtered by `RUST_LOG`, the spans of this crate only.
+	let otel_layer = match &config.OTLP_ENDPOINT {
+		Some(endpoint) => {
+			let tracer = new_otlp_tracer(endpoint, &config.OTLP_SERVICE_NAME)?;
+			let layer = tracing_opentelemetry::layer()
+				.with_tracer(tracer)
+				.with_filter(Targets::new().with_target(CRATE_TARGET, Level::INFO));
+			Some(layer)
+		}
+		None => None,
+	};
+
+	tracing_subscriber::registry()
+		.with(fmt_layer)
+		.with(otel_layer)
+		.init();
+
+	Ok(())
+}
+
+/// Exports the pending spans (e.g., on shutdown).
+pub async fn shutdown_tracing() {
+	// Note: Blocks until exported, hence on a blocking thread.
+	let _ =
+		tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider)
+			.await;
+}
+
+/// The trace context of the incoming W3C `traceparent` header, to continue
+/// the caller trace (empty without the header).
+pub fn parent_context(headers: &HeaderMap) -> Context {
+	TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+// region:    --- Support
+
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// Note: Installs the tracer provider globally (see `shutdown_tracing`).
+fn new_otlp_tracer(endpoint: &str, service_name: &str) -> Result<sdktrace::Tracer> {
+	let exporter = opentelemetry_otlp::new_exporter()
+		.http()
+		.with_endpoint(endpoint)
+		.with_http_client(HyperClient::new_with_timeout(
+			Client::new(),
+			EXPORT_TIMEOUT,
+		));
+	let service_name = KeyValue::new("service.name", service_name.to_string());
+	let trace_config =
+		sdktrace::config().with_resource(Resource::new([service_name]));
+
+	opentelemetry_otlp::new_pipeline()
+		.tracing()
+		.with_exporter(exporter)
+		.with_trace_config(trace_config)
+		.install_batch(runtime::Tokio)
+		.map_err(|ex| Error::OtlpInstall(ex.to_string()))
+}
+
+// endregion: --- Support
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use axum::body::Bytes;
+	use axum::extract::State;
+	use axum::routing::post;
+	use axum::Router;
+	use opentelemetry::trace::TraceContextExt;
+	use std::net::SocketAddr;
+	use std::sync::{Arc, Mutex};
+	use tracing::info_span;
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+	const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+	#[test]
+	fn test_trace_parent_context_from_traceparent() -> Result<()> {
+		let mut headers = HeaderMap::new();
+		headers.insert(
+			"traceparent",
+			format!("00-{TRACE_ID}-00f067aa0ba902b7-01").parse()?,
+		);
+
+		let cx = parent_context(&headers);
+
+		let span_context = cx.span().span_context().clone();
+		assert!(span_context.is_remote(), "remote parent");
+		assert_eq!(span_context.trace_id().to_string(), TRACE_ID);
+		assert!(!parent_context(&HeaderMap::new()).has_active_span());
+
+		Ok(())
+	}
+
+	type Received = Arc<Mutex<Vec<Bytes>>>;
+
+	/// A local collector stand-in, keeping the otlp/http export bodies.
+	async fn spawn_mock_collector() -> Result<(String, Received)> {
+		let received: Received = Arc::default();
+		let app = Router::new()
+			.route(
+				"/v1/traces",
+				post(|State(received): State<Received>, body: Bytes| async move {
+					received.lock().unwrap().push(body);
+				}),
+			)
+			.with_state(received.clone());
+		let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+			.serve(app.into_make_service());
+		let endpoint = format!("http://{}", server.local_addr());
+		tokio::spawn(server);
+
+		Ok((endpoint, received))
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn test_trace_otlp_export_with_parent() -> Result<()> {
+		let (endpoint, received) = spawn_mock_collector().await?;
+		let tracer = new_otlp_tracer(&endpoint, "test_trace_service")?;
+		let subscriber = tracing_subscriber::registry()
+			.with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+		// -- Exec
+		let mut headers = HeaderMap::new();
+		headers.insert(
+			"traceparent",
+			format!("00-{TRACE_ID}-00f067aa0ba902b7-01").parse()?,
+		);
+		tracing::subscriber::with_default(subscriber, || {
+			let span = info_span!("test_trace_span");
+			span.set_parent(parent_context(&headers));
+			span.in_scope(|| info_span!("test_trace_child").in_scope(|| {}));
+		});
+		shutdown_tracing().await;
+
+		// -- Check (the protobuf has the names and trace id as raw bytes)
+		let body: Vec<u8> = received.lock().unwrap().concat();
+		let contains = |bytes: &[u8]| body.windows(bytes.len()).any(|w| w == bytes);
+		assert!(contains(b"test_trace_service"), "service name exported");
+		assert!(contains(b"test_trace_span"), "span exported");
+		assert!(contains(b"test_trace_child"), "child span exported");
+		let trace_id: Vec<u8> = (0..TRACE_ID.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&TRACE_ID[i..i + 2], 16))
+			.collect::<core::result::Result<_, _>>()?;
+		assert!(contains(&trace_id), "parent trace id continued");
+
+		Ok(())
+	}
+}
@@ -1,5 +1,6 @@
 mod error;
 
+use serde::{Deserialize, Deserializer, Serializer};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 pub use self::error::{Error, Result};
@@ -12,6 +13,46 @@ pub fn format_time(time: OffsetDateTime) -> String {
 	time.format(&Rfc3339).unwrap()
 }
 
+/// Serializes an `OffsetDateTime` with `format_time` (Rfc3339).
+/// Usage: `#[serde(serialize_with = "crate::utils::serialize_time")]`
+pub fn serialize_time<S>(
+	time: &OffsetDateTime,
+	serializer: S,
+) -> core::result::Result<S::Ok, S::Error>
+where
+	S: Serializer,
+{
+	serializer.serialize_str(&format_time(*time))
+}
+
+/// Option variant of `serialize_time`.
+pub fn serialize_time_opt<S>(
+	time: &Option<OffsetDateTime>,
+	serializer: S,
+) -> core::result::Result<S::Ok, S::Error>
+where
+	S: Serializer,
+{
+	match time {
+		Some(time) => serialize_time(time, serializer),
+		None => serializer.serialize_none(),
+	}
+}
+
+/// Deserializes an optional Rfc3339 string with `parse_utc_str`.
+/// Usage: `#[serde(default, deserialize_with = "...")]`
+pub fn deserialize_time_opt<'de, D>(
+	deserializer: D,
+) -> core::result::Result<Option<OffsetDateTime>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let time_str: Option<String> = Option::deserialize(deserializer)?;
+	time_str
+		.map(|s| parse_utc_str(&s).map_err(serde::de::Error::custom))
+		.transpose()
+}
+
 pub fn now_utc_plus_secs_str(secs: f64) -> String {
 	format_time(now_utc() + time::Duration::seconds_f64(secs))
 }
@@ -2,6 +2,7 @@ use crate::{crypt, model, web};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use serde::Serialize;
+use serde_json::Value;
 use tracing::debug;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -13,8 +14,10 @@ pub enum Error {
 	LoginFailUsernameNotFound,
 	LoginFailUserHasNoPwd { user_id: i64 },
 	LoginFailPwdNotMatching { user_id: i64 },
+	LoginFailUserDisabled { user_id: i64 },
 	// -- CtxExtError
 	CtxExt(web::mw_auth::CtxExtError),
+	ReqStampNotInReqExt,
 	Model(model::Error),
 	Crypt(crypt::Error),
 	RpcMethodNotFound(String),
@@ -81,7 +84,8 @@ impl Error {
 			// -- Login
 			LoginFailUsernameNotFound
 			| LoginFailUserHasNoPwd { .. }
-			| LoginFailPwdNotMatching { .. } => {
+			| LoginFailPwdNotMatching { .. }
+			| LoginFailUserDisabled { .. } => {
 				(StatusCode::BAD_REQUEST, ClientError::LOGIN_FAIL)
 			}
 			// -- Auth
@@ -91,6 +95,58 @@ impl Error {
 				StatusCode::NOT_FOUND,
 				ClientError::EntityNotFound { entity, id: *id },
 			),
+			Model(model::Error::AccessDenied { .. }) => {
+				(StatusCode::FORBIDDEN, ClientError::ACCESS_DENIED)
+			}
+			Model(model::Error::CycleDetected {
+				entity,
+				id,
+				target_id,
+			}) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::CycleDetected {
+					entity,
+					id: *id,
+					target_id: *target_id,
+				},
+			),
+			Model(model::Error::VersionConflict {
+				entity,
+				id,
+				current,
+				..
+			}) => (
+				StatusCode::CONFLICT,
+				ClientError::VersionConflict {
+					entity,
+					id: *id,
+					current: current.clone(),
+				},
+			),
+			Model(model::Error::BulkFailed { entity, errors }) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::BulkFailed {
+					entity,
+					errors: errors.iter().map(ClientBulkItemError::from).collect(),
+				},
+			),
+			Model(model::Error::BulkLimitExceeded { max, actual }) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::BulkLimitExceeded {
+					max: *max,
+					actual: *actual,
+				},
+			),
+			Model(model::Error::InvalidCursor { .. }) => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_CURSOR)
+			}
+			// e.g., a restore/purge on an entity without soft delete
+			Model(model::Error::SoftDeleteNotSupported { .. }) => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_INPUT)
+			}
+			Model(err) if err.as_sqlx().is_some_and(is_invalid_input) => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_INPUT)
+			}
 
 			// -- Fallback.
 			_ => (
@@ -107,7 +163,167 @@ impl Error {
 pub enum ClientError {
 	LOGIN_FAIL,
 	NO_AUTH,
+	ACCESS_DENIED,
+	INVALID_CURSOR,
+	INVALID_INPUT,
 	SERVICE_ERROR,
 	EntityNotFound { entity: &'static str, id: i64 },
+	CycleDetected {
+		entity: &'static str,
+		id: i64,
+		target_id: i64,
+	},
+	/// `current` is the current entity (with its version).
+	VersionConflict {
+		entity: &'static str,
+		id: i64,
+		current: Value,
+	},
+	BulkFailed {
+		entity: &'static str,
+		errors: Vec<ClientBulkItemError>,
+	},
+	BulkLimitExceeded {
+		max: i64,
+		actual: i64,
+	},
+}
+
+/// The client-safe error of one item of a bulk operation
+/// (the full model error only goes to the server log).
+#[derive(Debug, Serialize)]
+pub struct ClientBulkItemError {
+	index: usize,
+	error: ClientError,
+}
+
+impl From<&model::BulkItemError> for ClientBulkItemError {
+	fn from(item: &model::BulkItemError) -> Self {
+		use model::Error as M;
+
+		let error = match &item.error {
+			M::EntityNotFound { entity, id } => {
+				ClientError::EntityNotFound { entity, id: *id }
+			}
+			M::AccessDenied { .. } => ClientError::ACCESS_DENIED,
+			M::CycleDetected {
+				entity,
+				id,
+				target_id,
+			} => ClientError::CycleDetected {
+				entity,
+				id: *id,
+				target_id: *target_id,
+			},
+			M::VersionConflict {
+				entity,
+				id,
+				current,
+				..
+			} => ClientError::VersionConflict {
+				entity,
+				id: *id,
+				current: current.clone(),
+			},
+			err if err.as_sqlx().is_some_and(is_invalid_input) => {
+				ClientError::INVALID_INPUT
+			}
+			_ => ClientError::SERVICE_ERROR,
+		};
+
+		Self {
+			index: item.index,
+			error,
+		}
+	}
+}
+
+/// Constraint violations caused by the request (or bulk item) data
+/// (e.g., unique, foreign key).
+fn is_invalid_input(err: &sqlx::Error) -> bool {
+	use sqlx::error::ErrorKind;
+
+	let sqlx::Error::Database(db_err) = err else {
+		return false;
+	};
+	matches!(
+		db_err.kind(),
+		ErrorKind::UniqueViolation
+			| ErrorKind::ForeignKeyViolation
+			| ErrorKind::NotNullViolation
+			| ErrorKind::CheckViolation
+	)
 }
 // endregion: --- Client Error
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::label::{LabelBmc, LabelForCreate};
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_client_error_unique_violation_invalid_input() -> Result<()> {
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_name = "test_client_error_label";
+		let label = LabelBmc::create(
+			&ctx,
+			&mm,
+			LabelForCreate {
+				name: fx_name.to_string(),
+				..Default::default()
+			},
+		)
+		.await?;
+
+		// Execute
+		let res = LabelBmc::create(
+			&ctx,
+			&mm,
+			LabelForCreate {
+				name: fx_name.to_string(),
+				..Default::default()
+			},
+		)
+		.await;
+
+		// Check
+		let Err(err) = res else {
+			panic!("duplicate label name should fail");
+		};
+		let (status, client_error) = Error::from(err).client_status_and_error();
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+		assert!(
+			matches!(client_error, ClientError::INVALID_INPUT),
+			"client error {client_error:?}"
+		);
+
+		// Cleanup
+		LabelBmc::delete(&ctx, &mm, label.id).await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_client_error_caller_errors_bad_request() -> Result<()> {
+		let errors = [
+			model::Error::SoftDeleteNotSupported { entity: "label" },
+			model::Error::BulkLimitExceeded {
+				max: 1000,
+				actual: 1001,
+			},
+		];
+
+		for err in errors {
+			let (status, _) = Error::from(err).client_status_and_error();
+			assert_eq!(status, StatusCode::BAD_REQUEST);
+		}
+
+		Ok(())
+	}
+}
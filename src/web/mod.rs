@@ -2,8 +2,11 @@
 
 mod error;
 pub mod mw_auth;
+pub mod mw_req_stamp;
 pub mod mw_res_map;
+pub mod routes_health;
 pub mod routes_login;
+pub mod routes_metrics;
 pub mod routes_static;
 
 use tower_cookies::{Cookie, Cookies};
@@ -67,6 +67,10 @@ async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResul
 			.map_err(|err| CtxExtError::ModelAccessError(err.to_string()))?
 			.ok_or(CtxExtError::UserNotFound)?;
 
+	if user.disabled {
+		return Err(CtxExtError::UserDisabled);
+	}
+
 	let salt = &user.token_salt.to_string();
 
 	validate_web_token(&token, salt).map_err(|_| CtxExtError::FailValidate)?;
@@ -74,7 +78,12 @@ async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResul
 	set_token_cookies(cookies, &user.username, salt)
 		.map_err(|_| CtxExtError::SetTokenToCookieFail)?;
 
-	Ctx::new(user.id).map_err(|err| CtxExtError::CtxCreateFail(err.to_string()))
+	let ctx = if user.admin {
+		Ctx::new_admin(user.id)
+	} else {
+		Ctx::new(user.id)
+	};
+	ctx.map_err(|err| CtxExtError::CtxCreateFail(err.to_string()))
 }
 
 // region:    --- Ctx Extractor
@@ -106,6 +115,7 @@ pub enum CtxExtError {
 	TokenWrongFormat,
 	ModelAccessError(String),
 	UserNotFound,
+	UserDisabled,
 	FailValidate,
 	SetTokenToCookieFail,
 }
@@ -0,0 +1,95 @@
+use crate::trace::parent_context;
+use crate::utils::now_utc;
+use crate::web::{Error, Result};
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+use time::OffsetDateTime;
+use tracing::{debug, info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+/// The request id header, accepted from the client (or a proxy) and
+/// returned on every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The max length of an accepted request id.
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// The request id, and when the request came in (to measure its duration in
+/// `mw_reponse_map`).
+#[derive(Debug, Clone)]
+pub struct ReqStamp {
+	/// The incoming `REQUEST_ID_HEADER`, or a new uuid.
+	pub req_id: String,
+	pub time_in: Instant,
+	/// The same, as a date (e.g., for the request log line).
+	pub time_in_utc: OffsetDateTime,
+}
+
+/// Stamps the request, and runs it within a `request` span with its
+/// `req_id` (so the handler and db traces carry it), continuing the trace
+/// of the incoming `traceparent` header, if any.
+///
+/// Note: Must be the outermost layer, to stamp the request first.
+pub async fn mw_req_stamp<B>(mut req: Request<B>, next: Next<B>) -> Response {
+	debug!("{:<12} - mw_req_stamp", "MIDDLEWARE");
+
+	let req_id = req
+		.headers()
+		.get(REQUEST_ID_HEADER)
+		.and_then(|val| val.to_str().ok())
+		.filter(|req_id| is_valid_req_id(req_id))
+		.map(String::from)
+		.unwrap_or_else(|| Uuid::new_v4().to_string());
+	let span = info_span!(
+		"request",
+		req_id = %req_id,
+		method = %req.method(),
+		path = %req.uri().path(),
+	);
+	span.set_parent(parent_context(req.headers()));
+
+	req.extensions_mut().insert(ReqStamp {
+		req_id: req_id.clone(),
+		time_in: Instant::now(),
+		time_in_utc: now_utc(),
+	});
+
+	let mut res = next.run(req).instrument(span).await;
+	if let Ok(val) = HeaderValue::from_str(&req_id) {
+		res.headers_mut().insert(REQUEST_ID_HEADER, val);
+	}
+
+	res
+}
+
+/// Note: Bounded to a short, log and header safe, id.
+fn is_valid_req_id(req_id: &str) -> bool {
+	!req_id.is_empty()
+		&& req_id.len() <= REQUEST_ID_MAX_LEN
+		&& req_id.bytes().all(|b| {
+			b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':')
+		})
+}
+
+// region:    --- ReqStamp Extractor
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ReqStamp {
+	type Rejection = Error;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+		debug!("{:<12} - ReqStamp", "EXTRACTOR");
+
+		parts
+			.extensions
+			.get::<ReqStamp>()
+			.cloned()
+			.ok_or(Error::ReqStampNotInReqExt)
+	}
+}
+// endregion: --- ReqStamp Extractor
@@ -1,22 +1,31 @@
 use crate::ctx::Ctx;
-use crate::log::log_request;
+use crate::log::{log_request, HttpInfo};
+use crate::metric::{self, RequestMetric};
 use crate::rpc::RpcInfo;
-use crate::web;
-use axum::http::{Method, Uri};
+use crate::web::{self, mw_req_stamp::ReqStamp};
+use axum::body::HttpBody;
+use axum::extract::{ConnectInfo, MatchedPath};
+use axum::http::header::USER_AGENT;
+use axum::http::{HeaderMap, Method, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::{json, to_value};
+use std::net::SocketAddr;
 use tracing::debug;
-use uuid::Uuid;
 
+#[allow(clippy::too_many_arguments)] // axum extractors
 pub async fn mw_reponse_map(
 	ctx: Option<Ctx>,
 	uri: Uri,
 	req_method: Method,
+	matched_path: Option<MatchedPath>,
+	req_stamp: ReqStamp,
+	client_addr: Option<ConnectInfo<SocketAddr>>,
+	headers: HeaderMap,
 	res: Response,
 ) -> Response {
 	debug!("{:<12} - mw_reponse_map", "RES_MAPPER");
-	let uuid = Uuid::new_v4();
+	let req_id = req_stamp.req_id.as_str();
 
 	let rpc_info = res.extensions().get::<RpcInfo>();
 
@@ -39,7 +48,7 @@ pub async fn mw_reponse_map(
           "error": {
             "message": message,
             "data": {
-              "req_uuid": uuid.to_string(),
+              "req_uuid": req_id,
               "detail": detail
             }
           }
@@ -51,19 +60,40 @@ pub async fn mw_reponse_map(
 				(*status_code, Json(client_error_body)).into_response()
 			});
 
-	// -- Build and log the server log line.
+	// -- The final response attributes.
+	let duration = req_stamp.time_in.elapsed();
+	let final_res = error_response.as_ref().unwrap_or(&res);
+	let status = final_res.status();
+	let res_size = final_res.body().size_hint().exact();
+
+	// -- Record the metrics.
 	let client_error = client_status_error.unzip().1;
-	// TODO: Need to hander if log_request fail (but should not fail request)
-	let _ = log_request(
-		uuid,
-		req_method,
+	metric::record_request(RequestMetric {
+		http_path: matched_path.as_ref().map_or("<fallback>", |p| p.as_str()),
+		http_method: req_method.as_str(),
+		status: status.as_u16(),
+		rpc_method: rpc_info.map(|rpc| rpc.method.as_str()),
+		client_error: client_error.as_ref().map(|err| err.as_ref()),
+		duration,
+	});
+
+	// -- Build and log the server log line.
+	let http = HttpInfo {
+		method: req_method,
 		uri,
-		rpc_info,
-		ctx,
-		web_error,
-		client_error,
-	)
-	.await;
+		status,
+		time_in: req_stamp.time_in_utc,
+		duration,
+		client_addr: client_addr.map(|ConnectInfo(addr)| addr),
+		user_agent: headers
+			.get(USER_AGENT)
+			.and_then(|val| val.to_str().ok())
+			.map(String::from),
+		res_size,
+	};
+	// TODO: Need to hander if log_request fail (but should not fail request)
+	let _ =
+		log_request(req_id, http, rpc_info, ctx, web_error, client_error).await;
 
 	debug!("\n");
 
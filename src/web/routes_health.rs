@@ -0,0 +1,43 @@
+//! The liveness and readiness probes, outside of the auth and response
+//! mapping middlewares (see `main`).
+
+use crate::model::ModelManager;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tracing::warn;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/health/live", get(live_handler))
+		.route("/health/ready", get(ready_handler))
+		.with_state(mm)
+}
+
+/// The process is up (no dependency checked).
+async fn live_handler() -> Json<Value> {
+	Json(json!({ "status": "ok" }))
+}
+
+/// The db is reachable and migrated, 503 otherwise.
+async fn ready_handler(State(mm): State<ModelManager>) -> (StatusCode, Json<Value>) {
+	match mm.db_status().await {
+		Ok(db) if db.migrations.is_up_to_date() => {
+			(StatusCode::OK, Json(json!({ "status": "ok", "db": db })))
+		}
+		Ok(db) => (
+			StatusCode::SERVICE_UNAVAILABLE,
+			Json(json!({ "status": "migrations_not_applied", "db": db })),
+		),
+		Err(err) => {
+			// Note: The cause is only logged, the probe being unauthenticated.
+			warn!("{:<12} - ready_handler - {err:?}", "HEALTH");
+			(
+				StatusCode::SERVICE_UNAVAILABLE,
+				Json(json!({ "status": "db_unavailable" })),
+			)
+		}
+	}
+}
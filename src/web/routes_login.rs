@@ -1,7 +1,9 @@
 use crate::crypt::{pwd, EncryptContent};
 use crate::ctx::Ctx;
+use crate::metric;
 use crate::model::user::{UserBmc, UserForLogin};
 use crate::model::ModelManager;
+use crate::rpc::Me;
 use crate::web::{self, set_token_cookies, Error, Result};
 use axum::extract::State;
 use axum::routing::post;
@@ -27,6 +29,17 @@ async fn api_login_handler(
 ) -> Result<Json<Value>> {
 	debug!("{:<12} - api_login_handler", "HANDLER");
 
+	let res = login(mm, cookies, payload).await;
+	metric::record_login(res.is_ok());
+
+	res
+}
+
+async fn login(
+	mm: ModelManager,
+	cookies: Cookies,
+	payload: LoginPayload,
+) -> Result<Json<Value>> {
 	let LoginPayload { username, password } = payload;
 	let root_ctx = Ctx::root_ctx();
 
@@ -53,12 +66,22 @@ async fn api_login_handler(
 	)
 	.map_err(|_| Error::LoginFailPwdNotMatching { user_id })?;
 
+	// Note: After the pwd check, so that a disabled user cannot be told apart
+	//       without its credentials.
+	if user.disabled {
+		return Err(Error::LoginFailUserDisabled { user_id });
+	}
+
 	set_token_cookies(&cookies, &user.username, &user.token_salt.to_string());
 
+	// The same user and profile as the `get_me` rpc.
+	let me = Me::load(&root_ctx, &mm, user_id).await?;
+
 	// Create the success body.
 	let body = Json(json!({
 		"result": {
-			"success": true
+			"success": true,
+			"user": me
 		}
 	}));
 
@@ -0,0 +1,26 @@
+use crate::metric;
+use crate::model::ModelManager;
+use axum::extract::State;
+use axum::routing::get;
+use axum::Router;
+use metrics_exporter_prometheus::PrometheusHandle;
+
+#[derive(Clone)]
+struct MetricsState {
+	mm: ModelManager,
+	handle: PrometheusHandle,
+}
+
+/// The Prometheus scrape endpoint (`/metrics`), outside of the auth and
+/// response mapping middlewares, like the health probes.
+pub fn routes(mm: ModelManager, handle: PrometheusHandle) -> Router {
+	Router::new()
+		.route("/metrics", get(metrics_handler))
+		.with_state(MetricsState { mm, handle })
+}
+
+async fn metrics_handler(State(state): State<MetricsState>) -> String {
+	metric::record_db_pool(&state.mm.pool_status());
+
+	state.handle.render()
+}